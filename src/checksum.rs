@@ -0,0 +1,80 @@
+use anyhow::Result;
+
+/// Parse the SHA-256 digest from the content of a sidecar file.
+///
+/// Accepts both a bare hex digest and the `sha256sum` format
+/// (`<digest>  <filename>`); only the first token is used.
+///
+/// # Errors
+///
+/// Returns an error if the first token is not a 64 character hex digest.
+pub fn parse_sidecar(text: &str) -> Result<Vec<u8>> {
+    let token = text.split_whitespace().next().unwrap_or_default();
+
+    if token.len() != 64 {
+        anyhow::bail!("sidecar does not contain a SHA-256 hex digest");
+    }
+
+    // `from_str_radix` alone would also take a sign, e.g. `+f`.
+    if !token.bytes().all(|b| b.is_ascii_hexdigit()) {
+        anyhow::bail!("invalid hex digest in sidecar: {token}");
+    }
+
+    (0..token.len())
+        .step_by(2)
+        .map(|i| {
+            token
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| anyhow::anyhow!("invalid hex digest in sidecar: {token}"))
+        })
+        .collect()
+}
+
+/// Key of the object a checksum is verified for.
+///
+/// When the newest matching key is the sidecar itself (checks without a
+/// `suffix`), the key of the object it describes is returned instead.
+#[must_use]
+pub fn artefact_key<'a>(newest: &'a str, sidecar: &str) -> &'a str {
+    if sidecar.is_empty() {
+        return newest;
+    }
+    newest.strip_suffix(sidecar).unwrap_or(newest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    #[test]
+    fn test_parse_sidecar() {
+        let expected = parse_sidecar(DIGEST).unwrap_or_default();
+        assert_eq!(expected.len(), 32);
+        assert_eq!(expected.first(), Some(&0xb9));
+        assert_eq!(
+            parse_sidecar(&format!("{DIGEST}  dump.sql.gz\n")).unwrap_or_default(),
+            expected
+        );
+        assert_eq!(
+            parse_sidecar(&DIGEST.to_uppercase()).unwrap_or_default(),
+            expected
+        );
+        assert!(parse_sidecar("").is_err());
+        assert!(parse_sidecar("abc123  dump.sql.gz").is_err());
+        assert!(parse_sidecar(&DIGEST.replace('b', "z")).is_err());
+        assert!(parse_sidecar(&format!("+f{}", DIGEST.get(2..).unwrap_or_default())).is_err());
+    }
+
+    #[test]
+    fn test_artefact_key() {
+        assert_eq!(artefact_key("db/dump.sql.gz", ".sha256"), "db/dump.sql.gz");
+        assert_eq!(
+            artefact_key("db/dump.sql.gz.sha256", ".sha256"),
+            "db/dump.sql.gz"
+        );
+        assert_eq!(artefact_key("db/dump.sql.gz", ""), "db/dump.sql.gz");
+    }
+}
@@ -0,0 +1,393 @@
+use super::run::load_config;
+use crate::config;
+use crate::s3;
+use anyhow::Result;
+use aws_credential_types::credential_feature::AwsCredentialFeature;
+use aws_credential_types::provider::ProvideCredentials;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+
+/// Clock skew, in seconds, from which a warning is shown.
+const SKEW_WARNING: i64 = 60;
+/// Clock skew, in seconds, from which S3 rejects signed requests.
+const SKEW_LIMIT: i64 = 15 * 60;
+
+/// Diagnostic lines and the number of problems found.
+#[derive(Debug, Default)]
+struct Report {
+    out: String,
+    problems: usize,
+}
+
+impl Report {
+    fn line(&mut self, label: &str, text: &str) {
+        let _ = writeln!(self.out, "{label:<22}{text}");
+    }
+
+    fn fail(&mut self, label: &str, error: &str) {
+        self.problems += 1;
+        let _ = writeln!(self.out, "{label:<22}FAIL {error}");
+        if let Some(hint) = hint(error) {
+            let _ = writeln!(self.out, "{:<22}hint: {hint}", "");
+        }
+    }
+}
+
+/// Report how `s3mon` connects to S3 with `config` and whether every
+/// configured bucket can be accessed.
+///
+/// Shows the credential provider that resolved, the region, endpoint and
+/// addressing style, the clock skew against the `Date` header of the
+/// endpoint, and the outcome of `HeadBucket` and `ListObjectsV2` for every
+/// bucket and prefix.
+///
+/// # Errors
+///
+/// Returns an error if the config cannot be read or any probe failed.
+pub async fn doctor(config: &Path) -> Result<()> {
+    let yml = load_config(config)?;
+    let monitor = s3::Monitor::new(&yml).await?;
+    let mut report = Report::default();
+
+    credentials(&yml, &monitor, &mut report).await;
+    connection(&yml, &monitor, &mut report).await;
+    for (bucket, objects) in &yml.s3mon.buckets {
+        permissions(&monitor, bucket, objects, &mut report).await;
+    }
+
+    print!("{}", report.out);
+    if report.problems > 0 {
+        anyhow::bail!("doctor found {} problem(s)", report.problems);
+    }
+    Ok(())
+}
+
+async fn credentials(yml: &config::Config, monitor: &s3::Monitor, report: &mut Report) {
+    let Some(provider) = &monitor.credentials else {
+        report.fail("Credentials", "no credential provider configured");
+        return;
+    };
+    match provider.provide_credentials().await {
+        Ok(credentials) => {
+            let static_keys = !yml.s3mon.access_key.is_empty() && !yml.s3mon.secret_key.is_empty();
+            let profile = std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+            let source = credential_source(
+                static_keys,
+                credentials
+                    .get_property::<Vec<AwsCredentialFeature>>()
+                    .map_or(&[], Vec::as_slice),
+                &profile,
+            );
+            let key = credentials.access_key_id();
+            let tail = key.get(key.len().saturating_sub(4)..).unwrap_or_default();
+            report.line(
+                "Credentials",
+                &format!("{source} (access key id ending in {tail})"),
+            );
+        }
+        Err(e) => report.fail(
+            "Credentials",
+            &aws_sdk_s3::error::DisplayErrorContext(&e).to_string(),
+        ),
+    }
+}
+
+async fn connection(yml: &config::Config, monitor: &s3::Monitor, report: &mut Report) {
+    let region = monitor.s3.config().region().map(ToString::to_string);
+    match &region {
+        Some(region) => report.line("Region", region),
+        None => report.fail("Region", "not set; set `region` or AWS_REGION"),
+    }
+
+    let url = if yml.s3mon.endpoint.is_empty() {
+        let url = region.as_deref().map_or_else(
+            || "https://s3.amazonaws.com".to_string(),
+            |region| format!("https://s3.{region}.amazonaws.com"),
+        );
+        report.line("Endpoint", &format!("{url} (AWS)"));
+        url
+    } else {
+        report.line("Endpoint", &yml.s3mon.endpoint);
+        yml.s3mon.endpoint.clone()
+    };
+
+    addressing(monitor, report);
+
+    match clock_skew(&url).await {
+        Ok(skew) if skew.abs() >= SKEW_LIMIT => report.fail(
+            "Clock skew",
+            &format!("{skew:+}s against the server, requests will be rejected"),
+        ),
+        Ok(skew) if skew.abs() >= SKEW_WARNING => {
+            report.line("Clock skew", &format!("{skew:+}s against the server, WARN"));
+        }
+        Ok(skew) => report.line("Clock skew", &format!("{skew:+}s against the server")),
+        Err(e) => report.fail("Clock skew", &format!("{e:#}")),
+    }
+}
+
+/// Report whether `monitor` forces path-style addressing.
+fn addressing(monitor: &s3::Monitor, report: &mut Report) {
+    report.line(
+        "Path-style addressing",
+        if monitor.path_style {
+            "yes (forced for the custom endpoint)"
+        } else {
+            "no (virtual-hosted)"
+        },
+    );
+}
+
+async fn permissions(
+    monitor: &s3::Monitor,
+    bucket: &str,
+    objects: &[config::Object],
+    report: &mut Report,
+) {
+    let _ = writeln!(report.out, "\nBucket {bucket}");
+    match monitor.head_bucket(bucket).await {
+        Ok(()) => report.line("  HeadBucket", "OK"),
+        Err(e) => report.fail("  HeadBucket", &e.to_string()),
+    }
+
+    let prefixes: BTreeSet<&str> = objects.iter().map(|o| o.prefix.as_str()).collect();
+    for prefix in prefixes {
+        let label = format!("  ListObjectsV2 {prefix}");
+        match monitor.first_key(bucket, prefix).await {
+            Ok(Some(key)) => report.line(&label, &format!("OK, first key {key}")),
+            Ok(None) => report.line(&label, "OK, no objects"),
+            Err(e) => report.fail(&label, &e.to_string()),
+        }
+    }
+}
+
+/// Human readable name of the provider that resolved the credentials.
+fn credential_source(
+    static_keys: bool,
+    features: &[AwsCredentialFeature],
+    profile: &str,
+) -> String {
+    if static_keys {
+        return "static keys from the config file".to_string();
+    }
+
+    let mut sources: Vec<String> = vec![];
+    for feature in features {
+        let source = match feature {
+            AwsCredentialFeature::CredentialsCode => "static keys".to_string(),
+            AwsCredentialFeature::CredentialsEnvVars => {
+                "environment variables (AWS_ACCESS_KEY_ID)".to_string()
+            }
+            AwsCredentialFeature::CredentialsEnvVarsStsWebIdToken
+            | AwsCredentialFeature::CredentialsProfileStsWebIdToken
+            | AwsCredentialFeature::CredentialsStsAssumeRoleWebId => {
+                "web identity token".to_string()
+            }
+            AwsCredentialFeature::CredentialsStsAssumeRole
+            | AwsCredentialFeature::CredentialsStsAssumeRoleSaml => "assumed role".to_string(),
+            AwsCredentialFeature::CredentialsProfile
+            | AwsCredentialFeature::CredentialsProfileSourceProfile
+            | AwsCredentialFeature::CredentialsProfileNamedProvider => {
+                format!("profile '{profile}'")
+            }
+            AwsCredentialFeature::CredentialsProfileSso | AwsCredentialFeature::CredentialsSso => {
+                "SSO".to_string()
+            }
+            AwsCredentialFeature::CredentialsProfileProcess
+            | AwsCredentialFeature::CredentialsProcess => "credential process".to_string(),
+            AwsCredentialFeature::CredentialsHttp => "container credentials endpoint".to_string(),
+            AwsCredentialFeature::CredentialsImds => "instance role (IMDS)".to_string(),
+            _ => continue,
+        };
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+
+    if sources.is_empty() {
+        "default credential chain".to_string()
+    } else {
+        sources.join(" via ")
+    }
+}
+
+/// Seconds the clock of the server at `url` is ahead (positive) or behind
+/// (negative) the local clock, from the `Date` header of a `HEAD` request.
+async fn clock_skew(url: &str) -> Result<i64> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let before = Utc::now();
+    let response = client.head(url).send().await?;
+    let after = Utc::now();
+
+    let date = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| anyhow::anyhow!("no Date header in the response of {url}"))?;
+    let server = DateTime::parse_from_rfc2822(date)
+        .map_err(|e| anyhow::anyhow!("invalid Date header '{date}': {e}"))?;
+
+    let local = before + (after - before) / 2;
+    Ok((server.with_timezone(&Utc) - local).num_seconds())
+}
+
+/// Likely cause of a failed probe.
+fn hint(error: &str) -> Option<&'static str> {
+    [
+        (
+            "InvalidAccessKeyId",
+            "the access key id does not exist on this endpoint",
+        ),
+        (
+            "SignatureDoesNotMatch",
+            "the secret key does not match the access key id",
+        ),
+        (
+            "RequestTimeTooSkewed",
+            "synchronise the local clock, e.g. with NTP",
+        ),
+        (
+            "AuthorizationHeaderMalformed",
+            "the region does not match the region of the bucket",
+        ),
+        ("PermanentRedirect", "the bucket is in another region"),
+        ("HTTP 301", "the bucket is in another region"),
+        ("NoSuchBucket", "the bucket does not exist"),
+        ("404", "the bucket does not exist"),
+        (
+            "AccessDenied",
+            "the credentials lack permission for this call",
+        ),
+        ("403", "the credentials lack permission for this call"),
+        (
+            "dispatch failure",
+            "the endpoint cannot be reached; check `endpoint` and the network",
+        ),
+        (
+            "connect error",
+            "the endpoint cannot be reached; check `endpoint` and the network",
+        ),
+    ]
+    .into_iter()
+    .find(|(needle, _)| error.contains(needle))
+    .map(|(_, hint)| hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_credential_source() {
+        assert_eq!(
+            credential_source(true, &[], "default"),
+            "static keys from the config file"
+        );
+        assert_eq!(
+            credential_source(
+                false,
+                &[AwsCredentialFeature::CredentialsEnvVars],
+                "default"
+            ),
+            "environment variables (AWS_ACCESS_KEY_ID)"
+        );
+        assert_eq!(
+            credential_source(
+                false,
+                &[
+                    AwsCredentialFeature::CredentialsProfile,
+                    AwsCredentialFeature::CredentialsProfileSourceProfile,
+                    AwsCredentialFeature::CredentialsStsAssumeRole,
+                ],
+                "backup"
+            ),
+            "profile 'backup' via assumed role"
+        );
+        assert_eq!(
+            credential_source(false, &[AwsCredentialFeature::CredentialsImds], "default"),
+            "instance role (IMDS)"
+        );
+        assert_eq!(
+            credential_source(false, &[], "default"),
+            "default credential chain"
+        );
+    }
+
+    #[test]
+    fn test_hint() {
+        assert_eq!(
+            hint("403 AccessDenied: Access Denied"),
+            Some("the credentials lack permission for this call")
+        );
+        assert_eq!(hint("HTTP 301"), Some("the bucket is in another region"));
+        assert_eq!(
+            hint("403 SignatureDoesNotMatch: The request signature ..."),
+            Some("the secret key does not match the access key id")
+        );
+        assert_eq!(hint("500 InternalError: oops"), None);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = Report::default();
+        report.line("Region", "us-east-1");
+        report.fail("  HeadBucket", "404 NoSuchBucket: missing");
+        assert_eq!(report.problems, 1);
+        assert_eq!(
+            report.out,
+            "Region                us-east-1\n  \
+             HeadBucket          FAIL 404 NoSuchBucket: missing\n                      \
+             hint: the bucket does not exist\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_addressing() -> Result<()> {
+        let mut yml: config::Config =
+            serde_yaml::from_str("s3mon:\n  region: us-east-1\n  buckets: {}\n")?;
+        let mut report = Report::default();
+        addressing(&s3::Monitor::new(&yml).await?, &mut report);
+
+        yml.s3mon.endpoint = "http://localhost:9000".to_string();
+        addressing(&s3::Monitor::new(&yml).await?, &mut report);
+
+        assert_eq!(
+            report.out,
+            "Path-style addressing no (virtual-hosted)\n\
+             Path-style addressing yes (forced for the custom endpoint)\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clock_skew() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/", listener.local_addr()?);
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await?;
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await?;
+            let date = (Utc::now() + chrono::Duration::hours(1)).to_rfc2822();
+            stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 403 Forbidden\r\nDate: {date}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    )
+                    .as_bytes(),
+                )
+                .await
+        });
+
+        let skew = clock_skew(&url).await?;
+        assert!((3_598..=3_601).contains(&skew), "skew {skew}");
+        server.await??;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use crate::config;
+use crate::discover::{self, Connection};
+use crate::s3;
+use anyhow::Result;
+use chrono::Utc;
+
+/// List `bucket` (under `prefix`, if set) and print a proposed config with a
+/// check for every group of similar keys.
+///
+/// # Errors
+///
+/// Returns an error if the S3 client cannot be initialised or the bucket
+/// cannot be listed.
+pub async fn init(config: &config::Config, bucket: &str, prefix: &str) -> Result<()> {
+    let monitor = s3::Monitor::new(config).await?;
+    let objects = monitor.list_objects(bucket, prefix).await?;
+    tracing::info!(
+        "Listed {} objects bucket='{bucket}' prefix='{prefix}'",
+        objects.len()
+    );
+
+    let connection = Connection {
+        endpoint: config.s3mon.endpoint.clone(),
+        region: config.s3mon.region.clone(),
+    };
+    print!(
+        "{}",
+        discover::config_yaml(
+            bucket,
+            &connection,
+            objects.len(),
+            &discover::group(&objects),
+            Utc::now().timestamp(),
+        )
+    );
+    Ok(())
+}
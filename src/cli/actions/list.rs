@@ -0,0 +1,338 @@
+use super::run::{load_config, max_age};
+use crate::config;
+use crate::output::{format_columns, human_age, human_size};
+use crate::s3::{self, ObjectFilter, ObjectInfo};
+use anyhow::Result;
+use chrono::prelude::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::path::Path;
+
+const HEADER: [&str; 7] = [
+    "KEY",
+    "SIZE",
+    "LAST MODIFIED",
+    "AGE",
+    "SUFFIX",
+    "FRESH",
+    "SIZE OK",
+];
+
+/// Print the objects seen by every check of `config`, optionally limited to
+/// `bucket` and to prefixes starting with `prefix`, and why each check
+/// passes or fails.
+///
+/// Only the listing part of a check (suffix, age and size) is evaluated;
+/// content, checksum, compliance and retention rules are not.
+///
+/// # Errors
+///
+/// Returns an error if the config file cannot be read or parsed, the S3
+/// client cannot be initialised, or no check matches the filters.
+pub async fn list(config: &Path, bucket: Option<&str>, prefix: Option<&str>) -> Result<()> {
+    let yml = load_config(config)?;
+    let monitor = s3::Monitor::new(&yml).await?;
+
+    let mut found = false;
+    for (name, files) in &yml.s3mon.buckets {
+        if bucket.is_some_and(|bucket| bucket != name) {
+            continue;
+        }
+        for file in files
+            .iter()
+            .filter(|file| prefix.is_none_or(|prefix| file.prefix.starts_with(prefix)))
+        {
+            found = true;
+            print!("{}", describe(&monitor, name, file).await);
+        }
+    }
+
+    if !found {
+        anyhow::bail!("no configured check matches the given --bucket and --prefix");
+    }
+    Ok(())
+}
+
+/// Listing and verdict of `file`, once per child directory when it is a
+/// `per_subdirectory` check.
+async fn describe(monitor: &s3::Monitor, bucket: &str, file: &config::Object) -> String {
+    let now = Utc::now();
+    let mut out = String::new();
+
+    let prefixes = if file.per_subdirectory {
+        match monitor.list_subdirectories(bucket, &file.prefix).await {
+            Ok(children) if children.is_empty() => {
+                let _ = writeln!(
+                    out,
+                    "{bucket}/{}\nFAIL missing: no subdirectories under the prefix\n",
+                    file.prefix
+                );
+                return out;
+            }
+            Ok(mut children) => {
+                children.truncate(file.max_subdirectories);
+                children
+                    .into_iter()
+                    .map(|child| format!("{child}{}", file.subpath))
+                    .collect()
+            }
+            Err(e) => {
+                let _ = writeln!(out, "{bucket}/{}\nERROR: {e}\n", file.prefix);
+                return out;
+            }
+        }
+    } else {
+        vec![file.prefix.clone()]
+    };
+
+    let window = max_age(file, now)
+        .and_then(|(age, _)| Ok((age, ObjectFilter::new(&file.suffix, age, file.size)?)));
+    for prefix in prefixes {
+        let listing = match &window {
+            Ok((age, filter)) => monitor
+                .list_objects(bucket, &prefix)
+                .await
+                .map(|objects| listing(bucket, &prefix, filter, *age, &objects, now.timestamp())),
+            Err(e) => Err(anyhow::anyhow!("{e}")),
+        };
+        match listing {
+            Ok(listing) => out.push_str(&listing),
+            Err(e) => {
+                let _ = writeln!(out, "{bucket}/{prefix}\nERROR: {e}");
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Describe the filters, list `objects` with the outcome of each filter and
+/// end with the verdict of the check.
+fn listing(
+    bucket: &str,
+    prefix: &str,
+    filter: &ObjectFilter,
+    age: i64,
+    objects: &[ObjectInfo],
+    now: i64,
+) -> String {
+    let mut filters = vec![];
+    if !filter.suffix.is_empty() {
+        filters.push(format!("ending in \"{}\"", filter.suffix));
+    }
+    filters.push(format!("newer than {}", human_age(age)));
+    if filter.min_size > 0 {
+        filters.push(format!("at least {}", human_size(filter.min_size)));
+    }
+    let mut out = format!("{bucket}/{prefix}: objects {}\n", filters.join(", "));
+
+    if !objects.is_empty() {
+        let yes_no = |passed: bool| if passed { "yes" } else { "no" }.to_string();
+        let rows: Vec<Vec<String>> = objects
+            .iter()
+            .map(|o| {
+                vec![
+                    o.key.clone(),
+                    human_size(o.size),
+                    DateTime::from_timestamp(o.last_modified, 0).map_or_else(
+                        || o.last_modified.to_string(),
+                        |t| t.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    ),
+                    human_age(now - o.last_modified),
+                    yes_no(filter.matches_suffix(&o.key)),
+                    yes_no(filter.is_fresh(o.last_modified)),
+                    yes_no(filter.is_large_enough(Some(o.size))),
+                ]
+            })
+            .collect();
+        out.push_str(&format_columns(&HEADER, &rows));
+    }
+
+    out.push_str(&verdict(filter, age, objects, now));
+    out.push('\n');
+    out
+}
+
+/// Why the check passes or fails, following [`s3::Monitor::check_storage`].
+fn verdict(filter: &ObjectFilter, age: i64, objects: &[ObjectInfo], now: i64) -> String {
+    let matching: Vec<&ObjectInfo> = objects
+        .iter()
+        .filter(|o| filter.matches_suffix(&o.key))
+        .collect();
+    let fresh: Vec<&ObjectInfo> = matching
+        .iter()
+        .copied()
+        .filter(|o| filter.is_fresh(o.last_modified))
+        .collect();
+
+    if objects.is_empty() {
+        return "FAIL missing: no objects under the prefix".to_string();
+    }
+    if matching.is_empty() {
+        return format!(
+            "FAIL missing: {} listed, none ends in \"{}\"",
+            count(objects.len()),
+            filter.suffix
+        );
+    }
+    if fresh.is_empty() {
+        let (key, modified) = newest(&matching)
+            .map(|o| (o.key.as_str(), o.last_modified))
+            .unwrap_or_default();
+        return format!(
+            "FAIL missing: {} matching, none is newer than {}; the newest, {key}, is {} old",
+            count(matching.len()),
+            human_age(age),
+            human_age(now - modified)
+        );
+    }
+
+    let large: Vec<&ObjectInfo> = fresh
+        .iter()
+        .copied()
+        .filter(|o| filter.is_large_enough(Some(o.size)))
+        .collect();
+    let Some(passed) = newest(&large) else {
+        let largest = fresh.iter().map(|o| o.size).max().unwrap_or_default();
+        return format!(
+            "FAIL size mismatch: {} fresh, none is at least {}; the largest is {}",
+            count(fresh.len()),
+            human_size(filter.min_size),
+            human_size(largest)
+        );
+    };
+
+    format!(
+        "OK: {} is {} old and {}",
+        passed.key,
+        human_age(now - passed.last_modified),
+        human_size(passed.size)
+    )
+}
+
+fn newest<'a>(objects: &[&'a ObjectInfo]) -> Option<&'a ObjectInfo> {
+    objects.iter().copied().max_by_key(|o| o.last_modified)
+}
+
+fn count(objects: usize) -> String {
+    if objects == 1 {
+        "1 object".to_string()
+    } else {
+        format!("{objects} objects")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_760_752_800;
+
+    fn object(key: &str, size: i64, age: i64) -> ObjectInfo {
+        ObjectInfo {
+            key: key.to_string(),
+            size,
+            last_modified: NOW - age,
+            storage_class: None,
+        }
+    }
+
+    fn filter(suffix: &str, age: i64, min_size: i64) -> ObjectFilter {
+        ObjectFilter {
+            suffix: suffix.to_string(),
+            cutoff: NOW - age,
+            min_size,
+            stale_cutoff: None,
+        }
+    }
+
+    #[test]
+    fn test_listing() {
+        let objects = vec![
+            object("db/dump-1.sql.gz", 2048, 90_000),
+            object("db/dump-2.sql.gz", 512, 3_600),
+            object("db/notes.txt", 12, 60),
+        ];
+        let out = listing(
+            "backups",
+            "db/",
+            &filter(".sql.gz", 86_400, 1024),
+            86_400,
+            &objects,
+            NOW,
+        );
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines.first(),
+            Some(&"backups/db/: objects ending in \".sql.gz\", newer than 1d, at least 1.0 KiB")
+        );
+        assert!(
+            lines
+                .get(1)
+                .is_some_and(|l| l.contains("KEY") && l.contains("SIZE OK"))
+        );
+        let row = lines
+            .get(2)
+            .map(|l| l.split_whitespace().collect::<Vec<_>>());
+        assert_eq!(
+            row,
+            Some(vec![
+                "db/dump-1.sql.gz",
+                "2.0",
+                "KiB",
+                "2025-10-17",
+                "01:00:00",
+                "1d",
+                "1h",
+                "yes",
+                "no",
+                "yes"
+            ])
+        );
+        assert_eq!(
+            lines.last(),
+            Some(
+                &"FAIL size mismatch: 1 object fresh, none is at least 1.0 KiB; the largest is 512 B"
+            )
+        );
+    }
+
+    #[test]
+    fn test_verdict() {
+        let f = filter(".gz", 86_400, 0);
+        assert_eq!(
+            verdict(&f, 86_400, &[], NOW),
+            "FAIL missing: no objects under the prefix"
+        );
+        assert_eq!(
+            verdict(
+                &f,
+                86_400,
+                &[object("db/a.txt", 1, 60), object("db/b.txt", 1, 60)],
+                NOW
+            ),
+            "FAIL missing: 2 objects listed, none ends in \".gz\""
+        );
+        assert_eq!(
+            verdict(&f, 86_400, &[object("db/a.gz", 1, 90_000)], NOW),
+            "FAIL missing: 1 object matching, none is newer than 1d; the newest, db/a.gz, is 1d 1h old"
+        );
+        assert_eq!(
+            verdict(
+                &f,
+                86_400,
+                &[object("db/a.gz", 10, 7_200), object("db/b.gz", 0, 60)],
+                NOW
+            ),
+            "OK: db/b.gz is 1m old and 0 B"
+        );
+        assert_eq!(
+            verdict(
+                &filter("", 86_400, 5),
+                86_400,
+                &[object("db/a.gz", 10, 7_200), object("db/b.gz", 0, 60)],
+                NOW
+            ),
+            "OK: db/a.gz is 2h old and 10 B"
+        );
+    }
+}
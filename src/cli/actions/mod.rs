@@ -1,7 +1,13 @@
+pub mod doctor;
+pub mod init;
+pub mod list;
 pub mod run;
 
-use crate::output::OutputFormat;
+use crate::config;
+use crate::output::{OutputFormat, Precision};
+use crate::send::Target;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// All possible actions the CLI can perform.
 #[derive(Debug)]
@@ -11,5 +17,58 @@ pub enum Action {
         config: PathBuf,
         format: OutputFormat,
         exit_on_check_failure: bool,
+        /// Exit with `2` when a critical check fails, instead of `1`.
+        severity_exit_codes: bool,
+        /// Keep per-check history between runs in this file.
+        state_file: Option<PathBuf>,
+        options: OutputOptions,
     },
+    /// Keep running and print a fresh batch of results on every line read
+    /// from stdin, or every `interval` when set (Telegraf `execd` input).
+    Execd {
+        config: PathBuf,
+        format: OutputFormat,
+        interval: Option<Duration>,
+        state_file: Option<PathBuf>,
+        options: OutputOptions,
+    },
+    /// Run a single check given on the command line instead of a config file.
+    Check {
+        config: Box<config::Config>,
+        format: OutputFormat,
+        exit_on_check_failure: bool,
+        /// Exit with `2` when a critical check fails, instead of `1`.
+        severity_exit_codes: bool,
+        options: OutputOptions,
+    },
+    /// Propose a config from the objects found in a bucket.
+    Init {
+        /// Connection settings; its buckets are ignored.
+        config: Box<config::Config>,
+        bucket: String,
+        prefix: String,
+    },
+    /// Report credentials, connection settings and bucket permissions.
+    Doctor { config: PathBuf },
+    /// Print the objects each check sees and why it passes or fails.
+    List {
+        config: PathBuf,
+        /// Only checks of this bucket.
+        bucket: Option<String>,
+        /// Only checks whose prefix starts with this.
+        prefix: Option<String>,
+    },
+}
+
+/// Format-specific output settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputOptions {
+    /// Metric path template for the Graphite and `StatsD` formats.
+    pub path_template: String,
+    /// Send the output over the network instead of printing it.
+    pub send: Option<Target>,
+    /// Append the run start time to `InfluxDB` lines in this precision.
+    pub precision: Option<Precision>,
+    /// Add `age` and `size` float fields of the newest object to `InfluxDB` lines.
+    pub float_fields: bool,
 }
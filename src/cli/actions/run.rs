@@ -1,122 +1,1004 @@
-use crate::cli::actions::Action;
+use super::{doctor, init, list};
+use crate::checksum;
+use crate::cli::actions::{Action, OutputOptions};
 use crate::config;
-use crate::output::{CheckResult, OutputFormat, format_influxdb, format_prometheus};
-use crate::s3;
+use crate::content;
+use crate::notify::Notifier;
+use crate::output::{
+    CheckResult, InfluxOptions, OutputFormat, PostureResult, format_graphite, format_influxdb_with,
+    format_junit, format_nagios, format_nagios_unknown, format_posture_influxdb_with,
+    format_posture_prometheus, format_prometheus, format_statsd, format_table,
+};
+use crate::s3::{self, ObjectFilter};
+use crate::schedule;
+use crate::send;
+use crate::state::{self, State};
 use anyhow::Result;
+use chrono::prelude::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::time::MissedTickBehavior;
+
+/// Error returned when checks fail and `--exit-on-check-failure` is set.
+///
+/// Carries the severity the process exits with, see
+/// [`ChecksFailed::exit_code`]: the highest among the failures with the
+/// Nagios format or `--severity-exit-codes`, otherwise `Warning`, so that
+/// every failure exits `1` as before severities existed.
+#[derive(Debug)]
+pub struct ChecksFailed(pub config::Severity);
+
+impl ChecksFailed {
+    /// `2` when a critical check failed, `1` when only warnings failed.
+    #[must_use]
+    pub const fn exit_code(&self) -> u8 {
+        match self.0 {
+            config::Severity::Critical => 2,
+            config::Severity::Warning => 1,
+            config::Severity::Info => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for ChecksFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("one or more checks failed")
+    }
+}
+
+impl std::error::Error for ChecksFailed {}
+
+/// Error returned by the Nagios format when the checks could not run, so the
+/// process exits with the plugin `UNKNOWN` status instead of `1`.
+#[derive(Debug)]
+pub struct ChecksUnknown(pub anyhow::Error);
+
+impl std::fmt::Display for ChecksUnknown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ChecksUnknown {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
 
 /// Execute the given action.
 ///
 /// # Errors
 ///
-/// Returns an error if the config file cannot be read or parsed, or if the
-/// S3 client cannot be initialised.
+/// Returns an error if the config file cannot be read or parsed, if the
+/// S3 client cannot be initialised, or [`ChecksFailed`] when checks fail and
+/// the run should exit with a non-zero status.
 pub async fn execute(action: &Action) -> Result<()> {
     match action {
         Action::Monitor {
             config,
             format,
             exit_on_check_failure,
+            severity_exit_codes,
+            state_file,
+            options,
         } => {
-            let file = std::fs::File::open(config)
-                .map_err(|e| anyhow::anyhow!("cannot open config '{}': {e}", config.display()))?;
+            let outcome = match load_config(config) {
+                Ok(yml) => {
+                    monitor(
+                        yml,
+                        *format,
+                        *exit_on_check_failure,
+                        *severity_exit_codes,
+                        state_file.as_deref(),
+                        options,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            report_unknown(*format, outcome)
+        }
+        Action::Check {
+            config,
+            format,
+            exit_on_check_failure,
+            severity_exit_codes,
+            options,
+        } => {
+            let outcome = monitor(
+                (**config).clone(),
+                *format,
+                *exit_on_check_failure,
+                *severity_exit_codes,
+                None,
+                options,
+            )
+            .await;
+            report_unknown(*format, outcome)
+        }
+        Action::Execd {
+            config,
+            format,
+            interval,
+            state_file,
+            options,
+        } => execd(config, *format, *interval, state_file.as_deref(), options).await,
+        Action::Doctor { config } => doctor::doctor(config).await,
+        Action::Init {
+            config,
+            bucket,
+            prefix,
+        } => init::init(config, bucket, prefix).await,
+        Action::List {
+            config,
+            bucket,
+            prefix,
+        } => list::list(config, bucket.as_deref(), prefix.as_deref()).await,
+    }
+}
 
-            let yml: config::Config = serde_yaml::from_reader(file)
-                .map_err(|e| anyhow::anyhow!("error parsing config: {e}"))?;
+/// Severity the process exits with for a run whose worst failure is
+/// `severity`: unchanged with `by_severity`, otherwise `Warning` so that any
+/// failure exits `1`.
+const fn exit_severity(severity: config::Severity, by_severity: bool) -> config::Severity {
+    if by_severity {
+        severity
+    } else {
+        config::Severity::Warning
+    }
+}
 
-            let monitor = Arc::new(s3::Monitor::new(&yml).await?);
+/// Print the Nagios `UNKNOWN` status line for errors that kept the checks
+/// from running, so the plugin output is never empty, and return them as
+/// [`ChecksUnknown`].
+fn report_unknown(format: OutputFormat, outcome: Result<()>) -> Result<()> {
+    outcome.map_err(|e| {
+        if format == OutputFormat::Nagios && !e.is::<ChecksFailed>() {
+            print!("{}", format_nagios_unknown(&e));
+            ChecksUnknown(e).into()
+        } else {
+            e
+        }
+    })
+}
 
-            let mut tasks = vec![];
+/// Run every check of `yml` once, print the results in `format` (or send
+/// them to `options.send`) and report failures as [`ChecksFailed`].
+///
+/// With a `state_file`, state changes since the previous invocation are
+/// notified. The Nagios format always reports failures through the exit
+/// status by severity, as required by the plugin guidelines.
+async fn monitor(
+    yml: config::Config,
+    format: OutputFormat,
+    exit_on_check_failure: bool,
+    severity_exit_codes: bool,
+    state_file: Option<&Path>,
+    options: &OutputOptions,
+) -> Result<()> {
+    let monitor = Arc::new(s3::Monitor::new(&yml).await?);
+    let mut tracker = Tracker::new(History::new(state_file, false), &yml)?;
+    let started = Utc::now();
+    let (mut results, posture) = run_checks(&monitor, yml).await?;
+    tracker.track(&mut results).await?;
 
-            for (bucket_name, files) in yml.s3mon.buckets {
-                for file in files {
-                    let m = Arc::clone(&monitor);
-                    let bucket = bucket_name.clone();
-                    tasks.push(tokio::spawn(async move { check(&m, bucket, file).await }));
-                }
-            }
+    let output = render(format, &results, &posture, options, started);
+
+    if let Some(target) = &options.send {
+        send::send(target, &output).await?;
+    } else {
+        // Strips the table colors when stdout is not a terminal.
+        anstream::print!("{output}");
+    }
+
+    if exit_on_check_failure || format == OutputFormat::Nagios {
+        let posture_failure = has_posture_failures(&posture).then_some(config::Severity::Critical);
+        if let Some(severity) = worst_failure(&results).max(posture_failure) {
+            return Err(ChecksFailed(exit_severity(
+                severity,
+                severity_exit_codes || format == OutputFormat::Nagios,
+            ))
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep the S3 client alive and run the checks on every line read from stdin,
+/// or every `interval`, for Telegraf's `execd` input.
+///
+/// Credentials and TLS connections are set up once instead of per run. A
+/// failing run is logged and does not stop the process; in stdin mode it
+/// exits when stdin is closed.
+async fn execd(
+    config: &Path,
+    format: OutputFormat,
+    interval: Option<Duration>,
+    state_file: Option<&Path>,
+    options: &OutputOptions,
+) -> Result<()> {
+    let yml = load_config(config)?;
+    let monitor = Arc::new(s3::Monitor::new(&yml).await?);
+    let mut tracker = Tracker::new(History::new(state_file, true), &yml)?;
+    let mut out = anstream::stdout();
+
+    if let Some(interval) = interval {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            gather(&monitor, &yml, format, options, &mut tracker, &mut out).await;
+        }
+    }
+
+    let stdin = BufReader::new(tokio::io::stdin());
+    serve(
+        &monitor,
+        &yml,
+        format,
+        options,
+        &mut tracker,
+        stdin,
+        &mut out,
+    )
+    .await
+}
+
+/// Gather once for every line read from `input`, until it is closed.
+async fn serve<R: AsyncBufRead + Unpin, W: Write>(
+    monitor: &Arc<s3::Monitor>,
+    yml: &config::Config,
+    format: OutputFormat,
+    options: &OutputOptions,
+    tracker: &mut Tracker,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let mut lines = input.lines();
+    while lines.next_line().await?.is_some() {
+        gather(monitor, yml, format, options, tracker, out).await;
+    }
+    Ok(())
+}
+
+/// Run the checks once, notify about state changes since the previous run
+/// and write the output to `out` (or send it to `options.send`), logging
+/// failures instead of returning them.
+async fn gather<W: Write>(
+    monitor: &Arc<s3::Monitor>,
+    yml: &config::Config,
+    format: OutputFormat,
+    options: &OutputOptions,
+    tracker: &mut Tracker,
+    out: &mut W,
+) {
+    let started = Utc::now();
+    let outcome = async {
+        let (mut results, posture) = run_checks(monitor, yml.clone()).await?;
+        tracker.track(&mut results).await?;
+        let output = render(format, &results, &posture, options, started);
+        if let Some(target) = &options.send {
+            send::send(target, &output).await
+        } else {
+            out.write_all(output.as_bytes())?;
+            out.flush()?;
+            Ok(())
+        }
+    }
+    .await;
+
+    if let Err(e) = outcome {
+        tracing::error!("gather failed: {e}");
+    }
+}
+
+/// Where the run history of the checks is kept.
+#[derive(Debug)]
+enum History {
+    /// A single run without `--state-file` has no history.
+    None,
+    /// `--execd` without `--state-file` keeps it for the life of the process.
+    Memory(State),
+    /// The `--state-file`.
+    File(PathBuf),
+}
+
+impl History {
+    fn new(state_file: Option<&Path>, execd: bool) -> Self {
+        match state_file {
+            Some(path) => Self::File(path.to_path_buf()),
+            None if execd => Self::Memory(State::default()),
+            None => Self::None,
+        }
+    }
+}
+
+/// Follows the state of the checks between runs and notifies about changes.
+#[derive(Debug)]
+struct Tracker {
+    history: History,
+    notifier: Notifier,
+}
 
-            let mut results: Vec<CheckResult> = vec![];
-            for task in tasks {
-                results.push(task.await.map_err(|e| anyhow::anyhow!("task error: {e}"))?);
+impl Tracker {
+    fn new(history: History, yml: &config::Config) -> Result<Self> {
+        if matches!(history, History::None) {
+            if !yml.s3mon.notify.webhooks.is_empty() {
+                tracing::warn!(
+                    "notify.webhooks without --state-file never fire: there is no previous run to compare with"
+                );
             }
+            if yml.s3mon.notify.email.is_some() {
+                tracing::warn!(
+                    "notify.email without --state-file mails on every run with a failing check"
+                );
+            }
+        }
+        Ok(Self {
+            history,
+            notifier: Notifier::new(yml.s3mon.notify.clone())?,
+        })
+    }
 
-            let output = match format {
-                OutputFormat::Prometheus => format_prometheus(&results),
-                OutputFormat::Influxdb => format_influxdb(&results),
+    /// Record `results` in the history, if any, filling in their last
+    /// success, failing since and debounced status, and notify about changes
+    /// since the previous run.
+    async fn track(&mut self, results: &mut [CheckResult]) -> Result<()> {
+        let now = Utc::now().timestamp();
+        let previous = match &mut self.history {
+            History::None => None,
+            History::Memory(state) => Some(state.record(results, now)),
+            History::File(path) => Some(state::update(path, |state| state.record(results, now))?),
+        };
+        self.notifier.notify(previous.as_ref(), results).await;
+        Ok(())
+    }
+}
+
+/// Format the results of a run started at `started` in `format`.
+fn render(
+    format: OutputFormat,
+    results: &[CheckResult],
+    posture: &[PostureResult],
+    options: &OutputOptions,
+    started: DateTime<Utc>,
+) -> String {
+    match format {
+        OutputFormat::Prometheus => {
+            format_prometheus(results) + &format_posture_prometheus(posture)
+        }
+        OutputFormat::Influxdb => {
+            let influx = InfluxOptions {
+                timestamp: options.precision.map(|p| p.timestamp(started)),
+                float_fields: options.float_fields,
             };
+            format_influxdb_with(results, &influx) + &format_posture_influxdb_with(posture, &influx)
+        }
+        OutputFormat::Nagios => format_nagios(results, posture),
+        OutputFormat::Junit => format_junit(results, posture),
+        OutputFormat::Table => format_table(results, posture),
+        OutputFormat::Graphite => format_graphite(results, posture, &options.path_template),
+        OutputFormat::Statsd => format_statsd(results, posture, &options.path_template),
+    }
+}
 
-            print!("{output}");
+/// Read and parse the configuration file.
+pub(super) fn load_config(path: &Path) -> Result<config::Config> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("cannot open config '{}': {e}", path.display()))?;
 
-            if *exit_on_check_failure && has_check_failures(&results) {
-                anyhow::bail!("one or more checks failed");
-            }
+    serde_yaml::from_reader(file).map_err(|e| anyhow::anyhow!("error parsing config: {e}"))
+}
 
-            Ok(())
+/// Run the object checks and bucket posture checks of `yml` concurrently.
+async fn run_checks(
+    monitor: &Arc<s3::Monitor>,
+    yml: config::Config,
+) -> Result<(Vec<CheckResult>, Vec<PostureResult>)> {
+    let mut posture_tasks = vec![];
+
+    if let Some(bucket_checks) = yml.s3mon.bucket_checks {
+        for bucket_name in yml.s3mon.buckets.keys() {
+            let m = Arc::clone(monitor);
+            let bucket = bucket_name.clone();
+            let checks = bucket_checks.enabled();
+            posture_tasks.push(tokio::spawn(async move {
+                check_bucket(&m, bucket, checks).await
+            }));
         }
     }
+
+    let mut tasks = vec![];
+    let now = Utc::now();
+
+    for (bucket_name, files) in yml.s3mon.buckets {
+        for file in files {
+            let m = Arc::clone(monitor);
+            let bucket = bucket_name.clone();
+            let silenced = silenced(&yml.s3mon.silence, &bucket, &file, now);
+            let severity = file.severity;
+            let (fail_after, recover_after) = (file.fail_after, file.recover_after);
+            tasks.push(tokio::spawn(async move {
+                let mut results = if file.per_subdirectory {
+                    check_subdirectories(m, bucket, file).await
+                } else {
+                    vec![check(&m, bucket, file).await]
+                };
+                for result in &mut results {
+                    result.silenced = silenced;
+                    result.severity = severity;
+                    result.fail_after = fail_after;
+                    result.recover_after = recover_after;
+                }
+                results
+            }));
+        }
+    }
+
+    let mut results: Vec<CheckResult> = vec![];
+    for task in tasks {
+        results.extend(task.await.map_err(|e| anyhow::anyhow!("task error: {e}"))?);
+    }
+
+    let mut posture: Vec<PostureResult> = vec![];
+    for task in posture_tasks {
+        posture.extend(task.await.map_err(|e| anyhow::anyhow!("task error: {e}"))?);
+    }
+
+    Ok((results, posture))
 }
 
-fn has_check_failures(results: &[CheckResult]) -> bool {
+/// Whether a global or per-check silence is active for `file` at `now`.
+fn silenced(
+    global: &[config::Silence],
+    bucket: &str,
+    file: &config::Object,
+    now: chrono::DateTime<Utc>,
+) -> bool {
+    match global
+        .iter()
+        .chain(&file.silence)
+        .find(|s| s.is_active(now))
+    {
+        Some(silence) => {
+            tracing::info!(
+                "Check silenced bucket='{bucket}' prefix='{}' reason='{}'",
+                file.prefix,
+                silence.reason
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+/// Highest severity among the failed checks that are neither silenced nor
+/// `info`, or `None` when the run should succeed.
+fn worst_failure(results: &[CheckResult]) -> Option<config::Severity> {
     results
         .iter()
-        .any(|result| result.error || !result.exist || result.size_mismatch)
+        .filter(|result| result.counts_as_failure())
+        .map(|result| result.severity)
+        .max()
+}
+
+fn has_posture_failures(results: &[PostureResult]) -> bool {
+    results.iter().any(|result| result.error || !result.ok)
+}
+
+async fn check_bucket(
+    monitor: &s3::Monitor,
+    bucket: String,
+    checks: Vec<config::Posture>,
+) -> Vec<PostureResult> {
+    let mut results = Vec::with_capacity(checks.len());
+
+    for posture in checks {
+        let (ok, error) = match monitor.check_posture(&bucket, posture).await {
+            Ok(ok) => (ok, false),
+            Err(e) => {
+                tracing::error!(
+                    "Error checking {} posture bucket='{bucket}': {e}",
+                    posture.as_str()
+                );
+                (false, true)
+            }
+        };
+        results.push(PostureResult {
+            bucket: bucket.clone(),
+            check: posture.as_str().to_string(),
+            ok,
+            error,
+        });
+    }
+
+    results
+}
+
+/// Run `file` once for every child directory under its prefix.
+///
+/// Each result keeps the configured prefix and carries the child name in
+/// `subdir`.  At most `max_subdirectories` children (in key order) are
+/// checked; if none are found, or listing fails, a single result for the
+/// configured prefix is returned instead.
+async fn check_subdirectories(
+    monitor: Arc<s3::Monitor>,
+    bucket: String,
+    file: config::Object,
+) -> Vec<CheckResult> {
+    let mut children = match monitor.list_subdirectories(&bucket, &file.prefix).await {
+        Ok(children) => children,
+        Err(e) => {
+            tracing::error!(
+                "Error listing subdirectories bucket='{bucket}' prefix='{}': {e}",
+                file.prefix
+            );
+            return vec![CheckResult {
+                bucket,
+                prefix: file.prefix,
+                suffix: file.suffix,
+                error: true,
+                error_message: Some(e.to_string()),
+                ..CheckResult::default()
+            }];
+        }
+    };
+
+    if children.is_empty() {
+        tracing::warn!(
+            "No subdirectories found bucket='{bucket}' prefix='{}'",
+            file.prefix
+        );
+        return vec![CheckResult {
+            bucket,
+            prefix: file.prefix,
+            suffix: file.suffix,
+            ..CheckResult::default()
+        }];
+    }
+
+    if children.len() > file.max_subdirectories {
+        tracing::warn!(
+            "Found {} subdirectories bucket='{bucket}' prefix='{}', checking only the first {}",
+            children.len(),
+            file.prefix,
+            file.max_subdirectories
+        );
+        children.truncate(file.max_subdirectories);
+    }
+
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut subdirs = HashMap::with_capacity(children.len());
+    for (index, child) in children.into_iter().enumerate() {
+        let m = Arc::clone(&monitor);
+        let bucket = bucket.clone();
+        let subdir = child
+            .strip_prefix(&file.prefix)
+            .unwrap_or(&child)
+            .trim_end_matches('/')
+            .to_string();
+        let child_file = config::Object {
+            prefix: format!("{child}{}", file.subpath),
+            per_subdirectory: false,
+            ..file.clone()
+        };
+        let prefix = file.prefix.clone();
+        let label = subdir.clone();
+        let id = tasks
+            .spawn(async move {
+                CheckResult {
+                    prefix,
+                    subdir: label,
+                    ..check(&m, bucket, child_file).await
+                }
+            })
+            .id();
+        subdirs.insert(id, (index, subdir));
+    }
+
+    join_subdirectories(tasks, subdirs, &bucket, &file).await
+}
+
+/// Collect the results of the per-subdirectory `tasks`, spawned for the
+/// `subdirs` of `file` and keyed by task id to their listing position.
+///
+/// Results keep the listing order whatever order the tasks finish in.  A
+/// task that panicked or was cancelled yields an error result for its
+/// subdirectory, so the check is reported instead of vanishing.
+async fn join_subdirectories(
+    mut tasks: tokio::task::JoinSet<CheckResult>,
+    mut subdirs: HashMap<tokio::task::Id, (usize, String)>,
+    bucket: &str,
+    file: &config::Object,
+) -> Vec<CheckResult> {
+    let mut results: Vec<Option<CheckResult>> = std::iter::repeat_with(|| None)
+        .take(subdirs.len())
+        .collect();
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (id, result) = match joined {
+            Ok(joined) => joined,
+            Err(e) => {
+                let subdir = subdirs
+                    .get_mut(&e.id())
+                    .map(|(_, subdir)| std::mem::take(subdir))
+                    .unwrap_or_default();
+                tracing::error!(
+                    "Error checking bucket='{bucket}' prefix='{}' subdir='{subdir}': {e}",
+                    file.prefix
+                );
+                let result = CheckResult {
+                    bucket: bucket.to_string(),
+                    prefix: file.prefix.clone(),
+                    suffix: file.suffix.clone(),
+                    subdir,
+                    error: true,
+                    error_message: Some(e.to_string()),
+                    ..CheckResult::default()
+                };
+                (e.id(), result)
+            }
+        };
+        if let Some(slot) = subdirs
+            .get(&id)
+            .and_then(|(index, _)| results.get_mut(*index))
+        {
+            *slot = Some(result);
+        }
+    }
+    results.into_iter().flatten().collect()
 }
 
 async fn check(monitor: &s3::Monitor, bucket: String, file: config::Object) -> CheckResult {
-    let mut exist = false;
-    let mut size_mismatch = false;
-    let mut error = false;
+    let mut result = CheckResult {
+        bucket,
+        prefix: file.prefix.clone(),
+        suffix: file.suffix.clone(),
+        ..CheckResult::default()
+    };
 
-    match monitor
-        .check_storage(&bucket, &file.prefix, &file.suffix, file.age, file.size)
-        .await
+    let age = match max_age(&file, Utc::now()) {
+        Ok((age, expected)) => {
+            result.expected = expected;
+            age
+        }
+        Err(e) => {
+            record_error(&mut result, "evaluating schedule for", &e);
+            return result;
+        }
+    };
+
+    let stats = match ObjectFilter::new(&file.suffix, age, file.size)
+        .and_then(|filter| filter.with_max_object_age(file.max_object_age))
     {
+        // Every output reports the newest object and the count, so list
+        // every page rather than stopping at the first fresh object.
+        Ok(filter) => {
+            monitor
+                .scan(&result.bucket, &file.prefix, &filter, true)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match stats {
         Ok(stats) => {
-            exist = stats.exists;
-            if exist && file.size > 0 {
-                size_mismatch = !stats.any_large_enough;
+            result.exist = stats.exists;
+            if result.exist && file.size > 0 {
+                result.size_mismatch = !stats.any_large_enough;
+            }
+            let fresh = stats.newest.as_ref().filter(|_| stats.exists);
+            check_newest(monitor, &file, fresh, &mut result).await;
+            if let Some(stale) = stats.stale {
+                if let Some(oldest) = stale.oldest.as_ref().filter(|_| stale.count > 0) {
+                    tracing::warn!(
+                        "Found {} objects older than {}s bucket='{}' prefix='{}' oldest='{}'",
+                        stale.count,
+                        file.max_object_age,
+                        result.bucket,
+                        file.prefix,
+                        oldest.key
+                    );
+                }
+                result.stale_objects = Some(stale.count);
+                result.oldest = stale.oldest.map(|o| (o.key, o.last_modified));
             }
+            result.objects = stats.count;
+            result.newest = stats.newest;
+        }
+        Err(e) => record_error(&mut result, "checking", &e),
+    }
+
+    result
+}
+
+/// Maximum age in seconds of a fresh object at `now`, and the expected run
+/// (Unix seconds) when it follows a `schedule`.
+///
+/// # Errors
+///
+/// Returns an error if the schedule cannot be evaluated.
+pub(super) fn max_age(file: &config::Object, now: DateTime<Utc>) -> Result<(i64, Option<i64>)> {
+    if file.schedule.is_empty() {
+        return Ok((file.age, None));
+    }
+    let expected = schedule::expected_run(&file.schedule, file.grace, now)?;
+    Ok((now.timestamp() - expected, Some(expected)))
+}
+
+/// Log a failed S3 call for the check and flag the result as errored.
+fn record_error(result: &mut CheckResult, action: &str, e: &anyhow::Error) {
+    tracing::error!(
+        "Error {action} bucket='{}' prefix='{}': {e}",
+        result.bucket,
+        result.prefix
+    );
+    result.error = true;
+    result.error_message.get_or_insert_with(|| e.to_string());
+}
+
+/// Run the optional checks that inspect the newest matching object.
+async fn check_newest(
+    monitor: &s3::Monitor,
+    file: &config::Object,
+    newest: Option<&s3::ObjectInfo>,
+    result: &mut CheckResult,
+) {
+    let bucket = result.bucket.clone();
+
+    if let Some(content) = &file.content {
+        match check_content(monitor, &bucket, newest, content).await {
+            Ok(valid) => result.content_valid = valid,
+            Err(e) => record_error(result, "validating content", &e),
+        }
+    }
+
+    if let Some(sum) = &file.checksum {
+        match check_checksum(monitor, &bucket, newest, sum).await {
+            Ok(mismatch) => result.checksum_mismatch = mismatch,
+            Err(e) => record_error(result, "verifying checksum", &e),
         }
+    }
+
+    match check_compliance(monitor, &bucket, newest, file).await {
+        Ok(rules) => result.compliance = rules,
+        Err(e) => record_error(result, "checking compliance", &e),
+    }
+
+    if let Some(retention) = &file.retention {
+        match check_retention(monitor, &bucket, newest, retention).await {
+            Ok((days, violated)) => {
+                result.retention_days_remaining = days;
+                result.retention_violation = Some(violated);
+            }
+            Err(e) => record_error(result, "checking retention", &e),
+        }
+    }
+}
+
+/// Download the newest matching object and validate its content.
+///
+/// Returns `Ok(None)` when there is no object to validate, which is already
+/// reported as missing, `Ok(Some(false))` when the content is invalid, and an
+/// error only when the download itself fails.
+async fn check_content(
+    monitor: &s3::Monitor,
+    bucket: &str,
+    newest: Option<&s3::ObjectInfo>,
+    content: &config::Content,
+) -> Result<Option<bool>> {
+    let Some(newest) = newest else {
+        return Ok(None);
+    };
+
+    // Ranged requests on empty objects are rejected by S3.
+    let (data, truncated) = if newest.size > 0 {
+        monitor
+            .get_object_bytes(
+                bucket,
+                &newest.key,
+                content.max_bytes,
+                content::tail_len(content),
+            )
+            .await?
+    } else {
+        (Vec::new(), false)
+    };
+
+    match content::validate(content, &data, truncated) {
+        Ok(()) => Ok(Some(true)),
         Err(e) => {
-            tracing::error!(
-                "Error checking bucket='{bucket}' prefix='{}': {e}",
+            tracing::warn!(
+                "Content validation failed bucket='{bucket}' key='{}': {e}",
+                newest.key
+            );
+            Ok(Some(false))
+        }
+    }
+}
+
+/// Verify the newest matching object against its checksum sidecar.
+///
+/// Returns `Ok(None)` when there is no object, which is already reported as
+/// missing, and whether the digests differ otherwise.  A missing or
+/// unreadable sidecar is an error, like a failed S3 call.
+async fn check_checksum(
+    monitor: &s3::Monitor,
+    bucket: &str,
+    newest: Option<&s3::ObjectInfo>,
+    sum: &config::Checksum,
+) -> Result<Option<bool>> {
+    let Some(newest) = newest else {
+        return Ok(None);
+    };
+
+    let key = checksum::artefact_key(&newest.key, &sum.sidecar);
+    let sidecar_key = format!("{key}{}", sum.sidecar);
+
+    let sidecar = monitor
+        .get_sidecar(bucket, &sidecar_key)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("checksum sidecar '{sidecar_key}' is missing"))?;
+    let expected = checksum::parse_sidecar(&sidecar)
+        .map_err(|e| anyhow::anyhow!("invalid checksum sidecar '{sidecar_key}': {e}"))?;
+
+    let stored = match sum.mode {
+        config::ChecksumMode::Attributes => monitor.stored_sha256(bucket, key).await?,
+        config::ChecksumMode::Stream => None,
+    };
+
+    let actual = match stored {
+        Some(digest) => digest,
+        None => monitor.stream_sha256(bucket, key).await?,
+    };
+
+    if actual != expected {
+        tracing::warn!("Checksum mismatch bucket='{bucket}' key='{key}'");
+    }
+
+    Ok(Some(actual != expected))
+}
+
+/// Evaluate the storage class and encryption rules against the newest
+/// matching object, returning each configured rule and whether it was violated.
+///
+/// Rules are reported as violated when there is no object to inspect.
+async fn check_compliance(
+    monitor: &s3::Monitor,
+    bucket: &str,
+    newest: Option<&s3::ObjectInfo>,
+    file: &config::Object,
+) -> Result<BTreeMap<String, bool>> {
+    let mut rules = BTreeMap::new();
+
+    if !file.storage_class.is_empty() {
+        // ListObjectsV2 may omit the storage class for STANDARD objects.
+        let violated = newest.is_none_or(|n| {
+            let class = n.storage_class.as_deref().unwrap_or("STANDARD");
+            !file
+                .storage_class
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(class))
+        });
+        rules.insert("storage_class".to_string(), violated);
+    }
+
+    if !file.encryption.is_empty() {
+        let violated = match newest {
+            Some(n) => !monitor
+                .get_encryption(bucket, &n.key)
+                .await?
+                .is_some_and(|sse| sse.eq_ignore_ascii_case(&file.encryption)),
+            None => true,
+        };
+        rules.insert("encryption".to_string(), violated);
+    }
+
+    for (rule, violated) in &rules {
+        if *violated {
+            tracing::warn!(
+                "Compliance rule '{rule}' violated bucket='{bucket}' prefix='{}'",
                 file.prefix
             );
-            error = true;
         }
     }
 
-    CheckResult {
-        bucket,
-        prefix: file.prefix,
-        suffix: file.suffix,
-        exist,
-        error,
-        size_mismatch,
+    Ok(rules)
+}
+
+/// Verify the Object Lock retention and legal hold of the newest matching object.
+///
+/// Returns the days left on the retention (if any) and whether the
+/// requirements were violated; a missing object is a violation.  An
+/// out-of-range `min_days` is reported as an error, like a failed S3 call.
+async fn check_retention(
+    monitor: &s3::Monitor,
+    bucket: &str,
+    newest: Option<&s3::ObjectInfo>,
+    required: &config::Retention,
+) -> Result<(Option<f64>, bool)> {
+    let min_seconds = required.min_days.checked_mul(86400).ok_or_else(|| {
+        anyhow::anyhow!("invalid retention min_days value: {}", required.min_days)
+    })?;
+
+    let Some(newest) = newest else {
+        return Ok((None, true));
+    };
+
+    let retention = monitor.get_retention(bucket, &newest.key).await?;
+    let now = Utc::now().timestamp();
+
+    #[allow(clippy::cast_precision_loss)]
+    let days = retention
+        .as_ref()
+        .map(|r| (r.retain_until - now) as f64 / 86400.0);
+
+    let mut violated = retention.as_ref().is_none_or(|r| {
+        !r.mode.eq_ignore_ascii_case(&required.mode) || r.retain_until - now < min_seconds
+    });
+
+    if required.legal_hold && !monitor.get_legal_hold(bucket, &newest.key).await? {
+        violated = true;
     }
+
+    if violated {
+        tracing::warn!(
+            "Object Lock requirements not met bucket='{bucket}' key='{}'",
+            newest.key
+        );
+    }
+
+    Ok((days, violated))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::output::{Precision, format_influxdb};
     use aws_sdk_s3::config::{Credentials, Region};
     use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
     use aws_smithy_types::body::SdkBody;
     use chrono::prelude::{SecondsFormat, Utc};
+    use std::fmt::Write as _;
 
     fn make_monitor(status: u16, body: &str) -> s3::Monitor {
-        let http_client = StaticReplayClient::new(vec![ReplayEvent::new(
-            http::Request::builder()
-                .body(SdkBody::empty())
-                .map_err(|e| anyhow::anyhow!("{e}"))
-                .unwrap_or_else(|_| unreachable!()),
-            http::Response::builder()
-                .status(status)
-                .body(SdkBody::from(body))
-                .map_err(|e| anyhow::anyhow!("{e}"))
-                .unwrap_or_else(|_| unreachable!()),
-        )]);
+        make_monitor_with(vec![(status, SdkBody::from(body))])
+    }
+
+    fn make_monitor_with(responses: Vec<(u16, SdkBody)>) -> s3::Monitor {
+        make_monitor_with_responses(
+            responses
+                .into_iter()
+                .map(|(status, body)| {
+                    http::Response::builder()
+                        .status(status)
+                        .body(body)
+                        .map_err(|e| anyhow::anyhow!("{e}"))
+                        .unwrap_or_else(|_| unreachable!())
+                })
+                .collect(),
+        )
+    }
+
+    fn make_monitor_with_responses(responses: Vec<http::Response<SdkBody>>) -> s3::Monitor {
+        let http_client = StaticReplayClient::new(
+            responses
+                .into_iter()
+                .map(|response| {
+                    ReplayEvent::new(
+                        http::Request::builder()
+                            .body(SdkBody::empty())
+                            .map_err(|e| anyhow::anyhow!("{e}"))
+                            .unwrap_or_else(|_| unreachable!()),
+                        response,
+                    )
+                })
+                .collect(),
+        );
 
         let cfg = aws_sdk_s3::Config::builder()
             .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
@@ -127,29 +1009,87 @@ mod tests {
 
         s3::Monitor {
             s3: aws_sdk_s3::Client::from_conf(cfg),
+            credentials: None,
+            path_style: true,
         }
     }
 
-    #[tokio::test]
-    async fn check_object() {
-        let last_modified = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
-        let body = format!(
+    /// Two `ListObjectsV2` pages of dated dumps, the newest (`now`) last.
+    fn two_pages(now: i64) -> (String, String) {
+        let first = objects_body(&[
+            ("db/2026-10-16.gz", 10, now - 2 * 86_400),
+            ("db/2026-10-17.gz", 20, now - 86_400),
+        ])
+        .replace(
+            "<IsTruncated>false</IsTruncated>",
+            "<IsTruncated>true</IsTruncated><NextContinuationToken>2</NextContinuationToken>",
+        );
+        (first, objects_body(&[("db/2026-10-18.gz", 30, now)]))
+    }
+
+    /// Last modification of the old fixtures, 2019-10-14T08:52:23Z.
+    const OLD: i64 = 1_571_043_143;
+
+    /// `ListObjectsV2` page listing `objects` as `(key, size, last_modified)`,
+    /// with `last_modified` in Unix seconds.
+    fn objects_body(objects: &[(&str, usize, i64)]) -> String {
+        let contents = objects
+            .iter()
+            .fold(String::new(), |mut out, (key, size, last_modified)| {
+                let last_modified = DateTime::from_timestamp(*last_modified, 0)
+                    .unwrap_or_default()
+                    .to_rfc3339_opts(SecondsFormat::Millis, true);
+                let _ = write!(
+                    out,
+                    "<Contents><Key>{key}</Key><LastModified>{last_modified}</LastModified>\
+                     <Size>{size}</Size><StorageClass>STANDARD</StorageClass></Contents>"
+                );
+                out
+            });
+        format!(
             r#"<?xml version="1.0" encoding="UTF-8"?>
             <ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
               <Name>cubeta</Name>
-              <Prefix>E</Prefix>
-              <KeyCount>1</KeyCount>
-              <MaxKeys>3</MaxKeys>
+              <KeyCount>{}</KeyCount>
+              <MaxKeys>1000</MaxKeys>
               <IsTruncated>false</IsTruncated>
-              <Contents>
-                <Key>ExampleObject.txt</Key>
-                <LastModified>{last_modified}</LastModified>
-                <ETag>"599bab3ed2c697f1d26842727561fd94"</ETag>
-                <Size>857</Size>
-                <StorageClass>REDUCED_REDUNDANCY</StorageClass>
-              </Contents>
-            </ListBucketResult>"#
-        );
+              {contents}
+            </ListBucketResult>"#,
+            objects.len()
+        )
+    }
+
+    /// `ListObjectsV2` page with a single object modified now.
+    fn list_body(key: &str, size: usize) -> String {
+        objects_body(&[(key, size, Utc::now().timestamp())])
+    }
+
+    /// Delimited `ListObjectsV2` page listing the child `prefixes`.
+    fn subdirectories_body(prefixes: &[&str]) -> String {
+        let common = prefixes.iter().fold(String::new(), |mut out, prefix| {
+            let _ = write!(
+                out,
+                "<CommonPrefixes><Prefix>{prefix}</Prefix></CommonPrefixes>"
+            );
+            out
+        });
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+              <Name>cubeta</Name>
+              <Delimiter>/</Delimiter>
+              <KeyCount>{}</KeyCount>
+              <MaxKeys>1000</MaxKeys>
+              <IsTruncated>false</IsTruncated>
+              {common}
+            </ListBucketResult>"#,
+            prefixes.len()
+        )
+    }
+
+    #[tokio::test]
+    async fn check_object() {
+        let body = objects_body(&[("ExampleObject.txt", 857, Utc::now().timestamp())]);
 
         let monitor = Arc::new(make_monitor(200, &body));
         let file = config::Object {
@@ -157,33 +1097,98 @@ mod tests {
             suffix: String::new(),
             age: 30,
             size: 0,
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.exist);
+        assert!(!result.error);
+        assert!(!result.size_mismatch);
+    }
+
+    #[tokio::test]
+    async fn check_object_lists_every_page() {
+        let now = Utc::now().timestamp();
+        // Dated keys sort the newest object onto the last page.
+        let (first, second) = two_pages(now);
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(first)),
+            (200, SdkBody::from(second)),
+        ]);
+        let file = config::Object {
+            prefix: "db/".to_string(),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.exist && !result.error);
+        assert_eq!(result.objects, 3);
+        assert_eq!(
+            result.newest.map(|n| (n.key, n.size, n.last_modified)),
+            Some(("db/2026-10-18.gz".to_string(), 30, now))
+        );
+    }
+
+    #[tokio::test]
+    async fn float_fields_describe_the_newest_object_of_every_page() {
+        let now = Utc::now().timestamp();
+        let (first, second) = two_pages(now);
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(first)),
+            (200, SdkBody::from(second)),
+        ]);
+        let file = config::Object {
+            prefix: "db/".to_string(),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        let options = InfluxOptions {
+            timestamp: Some(1_760_752_800),
+            float_fields: true,
+        };
+        let out = format_influxdb_with(&[result], &options);
+        // The newest object is on the second page: modified `now`, 30 bytes.
+        let age = out
+            .split_once(",age=")
+            .and_then(|(_, rest)| rest.split_once(','))
+            .and_then(|(age, _)| age.parse::<f64>().ok());
+        assert!(age.is_some_and(|age| age < 60.0), "{out}");
+        assert!(out.ends_with(",size=30.0 1760752800\n"), "{out}");
+    }
+
+    #[tokio::test]
+    async fn check_object_schedule() {
+        let monitor = Arc::new(make_monitor(200, &list_body("db/dump.sql.gz", 10)));
+        let file = config::Object {
+            prefix: "db/".to_string(),
+            age: 1,
+            schedule: "* * * * *".to_string(),
+            grace: 120,
+            ..config::Object::default()
         };
         let result = check(&monitor, "cubeta".to_string(), file).await;
         assert!(result.exist);
         assert!(!result.error);
-        assert!(!result.size_mismatch);
+        let expected = result.expected.unwrap_or_default();
+        let now = Utc::now().timestamp();
+        assert!(expected <= now - 120 && expected > now - 240);
+    }
+
+    #[tokio::test]
+    async fn check_object_invalid_schedule() {
+        let monitor = Arc::new(make_monitor(200, &list_body("db/dump.sql.gz", 10)));
+        let file = config::Object {
+            prefix: "db/".to_string(),
+            schedule: "every night".to_string(),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.error);
+        assert!(!result.exist);
+        assert_eq!(result.expected, None);
     }
 
     #[tokio::test]
     async fn check_object_size_mismatch() {
-        let last_modified = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
-        let body = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-            <ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
-              <Name>cubeta</Name>
-              <Prefix>E</Prefix>
-              <KeyCount>1</KeyCount>
-              <MaxKeys>3</MaxKeys>
-              <IsTruncated>false</IsTruncated>
-              <Contents>
-                <Key>ExampleObject.txt</Key>
-                <LastModified>{last_modified}</LastModified>
-                <ETag>"599bab3ed2c697f1d26842727561fd94"</ETag>
-                <Size>857</Size>
-                <StorageClass>REDUCED_REDUNDANCY</StorageClass>
-              </Contents>
-            </ListBucketResult>"#
-        );
+        let body = objects_body(&[("ExampleObject.txt", 857, Utc::now().timestamp())]);
 
         let monitor = Arc::new(make_monitor(200, &body));
         let file = config::Object {
@@ -191,6 +1196,7 @@ mod tests {
             suffix: String::new(),
             age: 30,
             size: 1024,
+            ..config::Object::default()
         };
         let result = check(&monitor, "cubeta".to_string(), file).await;
         assert!(result.exist);
@@ -200,28 +1206,15 @@ mod tests {
 
     #[tokio::test]
     async fn check_object_age_expired() {
-        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
-            <ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
-              <Name>cubeta</Name>
-              <Prefix>E</Prefix>
-              <KeyCount>1</KeyCount>
-              <MaxKeys>3</MaxKeys>
-              <IsTruncated>false</IsTruncated>
-              <Contents>
-                <Key>ExampleObject.txt</Key>
-                <LastModified>2019-10-14T08:52:23.231Z</LastModified>
-                <ETag>"599bab3ed2c697f1d26842727561fd94"</ETag>
-                <Size>857</Size>
-                <StorageClass>REDUCED_REDUNDANCY</StorageClass>
-              </Contents>
-            </ListBucketResult>"#;
-
-        let monitor = Arc::new(make_monitor(200, body));
+        let body = objects_body(&[("ExampleObject.txt", 857, OLD)]);
+
+        let monitor = Arc::new(make_monitor(200, &body));
         let file = config::Object {
             prefix: "E".to_string(),
             suffix: String::new(),
             age: 30,
             size: 1024,
+            ..config::Object::default()
         };
         let result = check(&monitor, "cubeta".to_string(), file).await;
         assert!(!result.exist);
@@ -231,27 +1224,10 @@ mod tests {
 
     #[tokio::test]
     async fn check_object_mixed_sizes() {
-        let last_modified = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
-        let body = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-            <ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
-              <Name>cubeta</Name>
-              <Prefix>E</Prefix>
-              <KeyCount>2</KeyCount>
-              <MaxKeys>1000</MaxKeys>
-              <IsTruncated>false</IsTruncated>
-              <Contents>
-                <Key>SmallObject.txt</Key>
-                <LastModified>{last_modified}</LastModified>
-                <Size>500</Size>
-              </Contents>
-              <Contents>
-                <Key>LargeObject.txt</Key>
-                <LastModified>{last_modified}</LastModified>
-                <Size>2000</Size>
-              </Contents>
-            </ListBucketResult>"#
-        );
+        let body = objects_body(&[
+            ("SmallObject.txt", 500, Utc::now().timestamp()),
+            ("LargeObject.txt", 2000, Utc::now().timestamp()),
+        ]);
 
         let monitor = Arc::new(make_monitor(200, &body));
         let file = config::Object {
@@ -259,6 +1235,7 @@ mod tests {
             suffix: String::new(),
             age: 30,
             size: 1024,
+            ..config::Object::default()
         };
         let result = check(&monitor, "cubeta".to_string(), file).await;
         assert!(result.exist);
@@ -284,6 +1261,7 @@ mod tests {
             suffix: String::new(),
             age: 30,
             size: 512,
+            ..config::Object::default()
         };
         let result = check(&monitor, "cubeta".to_string(), file).await;
         assert!(!result.exist);
@@ -291,6 +1269,617 @@ mod tests {
         assert!(!result.size_mismatch);
     }
 
+    #[tokio::test]
+    async fn check_object_content_valid() {
+        let body = br#"{"tables": 42}"#;
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Export.json", body.len()))),
+            (200, SdkBody::from(&body[..])),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            content: Some(config::Content {
+                format: config::ContentFormat::Json,
+                marker: String::new(),
+                max_bytes: 1024,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.exist);
+        assert!(!result.error);
+        assert_eq!(result.content_valid, Some(true));
+    }
+
+    #[tokio::test]
+    async fn check_object_content_invalid() {
+        let body = b"not gzip at all";
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Dump.sql.gz", body.len()))),
+            (200, SdkBody::from(&body[..])),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            content: Some(config::Content {
+                format: config::ContentFormat::Gzip,
+                marker: String::new(),
+                max_bytes: 1024,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.exist);
+        assert!(!result.error);
+        assert_eq!(result.content_valid, Some(false));
+        assert_eq!(worst_failure(&[result]), Some(config::Severity::Critical));
+    }
+
+    #[tokio::test]
+    async fn check_object_content_without_objects() {
+        let body = objects_body(&[]);
+        let monitor = make_monitor(200, &body);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            content: Some(config::Content {
+                format: config::ContentFormat::Gzip,
+                marker: String::new(),
+                max_bytes: 1024,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.exist);
+        assert!(!result.error);
+        assert_eq!(result.content_valid, None);
+        assert_eq!(result.failures(), vec!["missing"]);
+    }
+
+    const HELLO_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    #[tokio::test]
+    async fn check_object_checksum_streamed() {
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Dump.sql.gz", 11))),
+            (200, SdkBody::from(format!("{HELLO_SHA256}  Dump.sql.gz\n"))),
+            (200, SdkBody::from("hello world")),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            suffix: ".gz".to_string(),
+            checksum: Some(config::Checksum {
+                sidecar: ".sha256".to_string(),
+                mode: config::ChecksumMode::Stream,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.exist);
+        assert!(!result.error);
+        assert_eq!(result.checksum_mismatch, Some(false));
+    }
+
+    #[tokio::test]
+    async fn check_object_checksum_mismatch() {
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Dump.sql.gz", 11))),
+            (200, SdkBody::from(HELLO_SHA256)),
+            (200, SdkBody::from("hello there")),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            checksum: Some(config::Checksum {
+                sidecar: ".sha256".to_string(),
+                mode: config::ChecksumMode::Stream,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.error);
+        assert_eq!(result.checksum_mismatch, Some(true));
+        assert_eq!(worst_failure(&[result]), Some(config::Severity::Critical));
+    }
+
+    #[tokio::test]
+    async fn check_object_checksum_missing_sidecar() {
+        let no_such_key = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Error>
+                <Code>NoSuchKey</Code>
+                <Message>The specified key does not exist.</Message>
+            </Error>"#;
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Dump.sql.gz", 11))),
+            (404, SdkBody::from(no_such_key)),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            checksum: Some(config::Checksum {
+                sidecar: ".sha256".to_string(),
+                mode: config::ChecksumMode::Attributes,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.exist);
+        assert!(result.error);
+        assert_eq!(
+            result.error_message.as_deref(),
+            Some("checksum sidecar 'Dump.sql.gz.sha256' is missing")
+        );
+        assert_eq!(result.checksum_mismatch, None);
+    }
+
+    #[tokio::test]
+    async fn check_object_checksum_without_objects() {
+        let body = objects_body(&[]);
+        let monitor = make_monitor(200, &body);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            checksum: Some(config::Checksum {
+                sidecar: ".sha256".to_string(),
+                mode: config::ChecksumMode::Attributes,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.exist);
+        assert!(!result.error);
+        assert_eq!(result.checksum_mismatch, None);
+        assert_eq!(result.failures(), vec!["missing"]);
+    }
+
+    #[tokio::test]
+    async fn check_object_storage_class_violation() {
+        // The fixture reports REDUCED_REDUNDANCY.
+        let monitor = make_monitor_with(vec![(
+            200,
+            SdkBody::from(list_body("Dump.sql.gz", 11).replace("STANDARD", "REDUCED_REDUNDANCY")),
+        )]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            storage_class: vec!["GLACIER_IR".to_string(), "STANDARD_IA".to_string()],
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.error);
+        assert_eq!(result.compliance.get("storage_class"), Some(&true));
+        assert_eq!(worst_failure(&[result]), Some(config::Severity::Critical));
+    }
+
+    #[tokio::test]
+    async fn check_object_encryption_compliant() {
+        let head = http::Response::builder()
+            .status(200)
+            .header("x-amz-server-side-encryption", "aws:kms")
+            .body(SdkBody::empty())
+            .map_err(|e| anyhow::anyhow!("{e}"))
+            .unwrap_or_else(|_| unreachable!());
+        let monitor = make_monitor_with_responses(vec![
+            http::Response::builder()
+                .status(200)
+                .body(SdkBody::from(list_body("Dump.sql.gz", 11)))
+                .map_err(|e| anyhow::anyhow!("{e}"))
+                .unwrap_or_else(|_| unreachable!()),
+            head,
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            storage_class: vec!["STANDARD".to_string()],
+            encryption: "aws:kms".to_string(),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.error);
+        assert_eq!(result.compliance.get("storage_class"), Some(&false));
+        assert_eq!(result.compliance.get("encryption"), Some(&false));
+        assert_eq!(worst_failure(&[result]), None);
+    }
+
+    fn retention_body(mode: &str, days: i64) -> String {
+        let until = (Utc::now() + chrono::Duration::days(days))
+            .to_rfc3339_opts(SecondsFormat::Millis, true);
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Retention xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+              <Mode>{mode}</Mode>
+              <RetainUntilDate>{until}</RetainUntilDate>
+            </Retention>"#
+        )
+    }
+
+    #[tokio::test]
+    async fn check_object_retention_compliant() {
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Dump.sql.gz", 11))),
+            (200, SdkBody::from(retention_body("COMPLIANCE", 40))),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            retention: Some(config::Retention {
+                mode: "COMPLIANCE".to_string(),
+                min_days: 30,
+                legal_hold: false,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.error);
+        assert_eq!(result.retention_violation, Some(false));
+        assert!(
+            result
+                .retention_days_remaining
+                .is_some_and(|d| d > 39.0 && d <= 40.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn check_object_retention_too_short() {
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Dump.sql.gz", 11))),
+            (200, SdkBody::from(retention_body("GOVERNANCE", 40))),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            retention: Some(config::Retention {
+                mode: "COMPLIANCE".to_string(),
+                min_days: 30,
+                legal_hold: false,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.error);
+        assert_eq!(result.retention_violation, Some(true));
+        assert_eq!(worst_failure(&[result]), Some(config::Severity::Critical));
+    }
+
+    #[tokio::test]
+    async fn check_object_retention_invalid_min_days() {
+        let body = list_body("Dump.sql.gz", 11);
+        let monitor = make_monitor(200, &body);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            retention: Some(config::Retention {
+                mode: "COMPLIANCE".to_string(),
+                min_days: i64::MAX,
+                legal_hold: false,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(result.error);
+        assert_eq!(
+            result.error_message,
+            Some(format!("invalid retention min_days value: {}", i64::MAX))
+        );
+        assert_eq!(result.retention_violation, None);
+    }
+
+    #[tokio::test]
+    async fn check_object_retention_not_configured() {
+        let no_lock = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Error>
+                <Code>NoSuchObjectLockConfiguration</Code>
+                <Message>The specified object does not have a ObjectLock configuration</Message>
+            </Error>"#;
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(list_body("Dump.sql.gz", 11))),
+            (404, SdkBody::from(no_lock)),
+        ]);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            retention: Some(config::Retention {
+                mode: "COMPLIANCE".to_string(),
+                min_days: 1,
+                legal_hold: false,
+            }),
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.error);
+        assert_eq!(result.retention_days_remaining, None);
+        assert_eq!(result.retention_violation, Some(true));
+    }
+
+    #[tokio::test]
+    async fn check_bucket_posture() {
+        let versioning = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <VersioningConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+              <Status>Enabled</Status>
+            </VersioningConfiguration>"#;
+        let no_lifecycle = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Error>
+                <Code>NoSuchLifecycleConfiguration</Code>
+                <Message>The lifecycle configuration does not exist</Message>
+            </Error>"#;
+        let denied = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Error>
+                <Code>AccessDenied</Code>
+                <Message>Access Denied</Message>
+            </Error>"#;
+        let monitor = make_monitor_with(vec![
+            (200, SdkBody::from(versioning)),
+            (404, SdkBody::from(no_lifecycle)),
+            (403, SdkBody::from(denied)),
+        ]);
+        let results = check_bucket(
+            &monitor,
+            "cubeta".to_string(),
+            vec![
+                config::Posture::Versioning,
+                config::Posture::Lifecycle,
+                config::Posture::Replication,
+            ],
+        )
+        .await;
+
+        let summary: Vec<(&str, bool, bool)> = results
+            .iter()
+            .map(|r| (r.check.as_str(), r.ok, r.error))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("versioning", true, false),
+                ("lifecycle", false, false),
+                ("replication", false, true),
+            ]
+        );
+        assert!(has_posture_failures(&results));
+        assert!(!has_posture_failures(results.get(..1).unwrap_or_default()));
+    }
+
+    #[tokio::test]
+    async fn check_object_per_subdirectory() {
+        let listing = subdirectories_body(&["tenants/alpha/", "tenants/beta/"]);
+        let monitor = Arc::new(make_monitor_with(vec![
+            (200, SdkBody::from(listing)),
+            (
+                200,
+                SdkBody::from(list_body("tenants/alpha/backups/a.gz", 1)),
+            ),
+        ]));
+        let file = config::Object {
+            prefix: "tenants/".to_string(),
+            subpath: "backups/".to_string(),
+            per_subdirectory: true,
+            max_subdirectories: 1,
+            ..config::Object::default()
+        };
+        let results = check_subdirectories(monitor, "cubeta".to_string(), file).await;
+        assert_eq!(results.len(), 1, "capped by max_subdirectories");
+        let result = results.first();
+        assert_eq!(result.map(|r| r.prefix.as_str()), Some("tenants/"));
+        assert_eq!(result.map(|r| r.subdir.as_str()), Some("alpha"));
+        assert!(result.is_some_and(|r| r.exist && !r.error));
+    }
+
+    #[tokio::test]
+    async fn check_object_per_subdirectory_without_children() {
+        let listing = subdirectories_body(&[]);
+        let monitor = Arc::new(make_monitor(200, &listing));
+        let file = config::Object {
+            prefix: "tenants/".to_string(),
+            per_subdirectory: true,
+            ..config::Object::default()
+        };
+        let results = check_subdirectories(monitor, "cubeta".to_string(), file).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
+    }
+
+    #[tokio::test]
+    async fn check_object_per_subdirectory_task_failure() {
+        let mut tasks = tokio::task::JoinSet::new();
+        let id = tasks.spawn(std::future::pending::<CheckResult>()).id();
+        tasks.abort_all();
+        let subdirs = HashMap::from([(id, (0, "alpha".to_string()))]);
+        let file = config::Object {
+            prefix: "tenants/".to_string(),
+            per_subdirectory: true,
+            ..config::Object::default()
+        };
+        let results = join_subdirectories(tasks, subdirs, "cubeta", &file).await;
+        assert_eq!(results.len(), 1);
+        let result = results.first();
+        assert_eq!(result.map(|r| r.subdir.as_str()), Some("alpha"));
+        assert!(result.is_some_and(|r| r.error && r.error_message.is_some()));
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
+    }
+
+    #[tokio::test]
+    async fn check_object_per_subdirectory_keeps_listing_order() {
+        let (done, wait) = tokio::sync::oneshot::channel();
+        let mut tasks = tokio::task::JoinSet::new();
+        // `alpha` only finishes once `beta` has.
+        let alpha = tasks
+            .spawn(async move {
+                let _ = wait.await;
+                CheckResult {
+                    subdir: "alpha".to_string(),
+                    ..CheckResult::default()
+                }
+            })
+            .id();
+        let beta = tasks
+            .spawn(async move {
+                let _ = done.send(());
+                CheckResult {
+                    subdir: "beta".to_string(),
+                    ..CheckResult::default()
+                }
+            })
+            .id();
+        let subdirs = HashMap::from([
+            (alpha, (0, "alpha".to_string())),
+            (beta, (1, "beta".to_string())),
+        ]);
+        let results =
+            join_subdirectories(tasks, subdirs, "cubeta", &config::Object::default()).await;
+        let order: Vec<&str> = results.iter().map(|r| r.subdir.as_str()).collect();
+        assert_eq!(order, ["alpha", "beta"]);
+    }
+
+    #[tokio::test]
+    async fn check_object_stale() {
+        let body = objects_body(&[
+            ("Eold.log", 857, OLD),
+            ("Eolder.log", 857, OLD - 365 * 86_400),
+        ]);
+        let monitor = make_monitor(200, &body);
+        let file = config::Object {
+            prefix: "E".to_string(),
+            max_object_age: 86400,
+            ..config::Object::default()
+        };
+        let result = check(&monitor, "cubeta".to_string(), file).await;
+        assert!(!result.exist);
+        assert!(!result.error);
+        assert_eq!(result.stale_objects, Some(2));
+        assert_eq!(
+            result.oldest.as_ref().map(|(key, _)| key.as_str()),
+            Some("Eolder.log")
+        );
+    }
+
+    #[test]
+    fn detects_check_failures_for_stale_objects() {
+        let results = vec![CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "prefix/".to_string(),
+            exist: true,
+            stale_objects: Some(1),
+            ..CheckResult::default()
+        }];
+
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
+    }
+
+    #[test]
+    fn uses_debounced_status_for_failures() {
+        let pending = CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "replica/".to_string(),
+            debounced: Some(false),
+            ..CheckResult::default()
+        };
+        assert!(!pending.failures().is_empty());
+        assert_eq!(worst_failure(std::slice::from_ref(&pending)), None);
+
+        let recovering = CheckResult {
+            exist: true,
+            debounced: Some(true),
+            ..pending
+        };
+        assert!(recovering.failures().is_empty());
+        assert_eq!(
+            worst_failure(&[recovering]),
+            Some(config::Severity::Critical)
+        );
+    }
+
+    #[test]
+    fn reports_worst_failure_severity() {
+        let failing = |prefix: &str, severity| CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: prefix.to_string(),
+            severity,
+            ..CheckResult::default()
+        };
+
+        let mut results = vec![failing("tmp/", config::Severity::Info)];
+        assert_eq!(worst_failure(&results), None);
+
+        results.push(failing("logs/", config::Severity::Warning));
+        assert_eq!(worst_failure(&results), Some(config::Severity::Warning));
+        assert_eq!(ChecksFailed(config::Severity::Warning).exit_code(), 1);
+
+        results.push(failing("db/", config::Severity::Critical));
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
+        assert_eq!(ChecksFailed(config::Severity::Critical).exit_code(), 2);
+    }
+
+    #[test]
+    fn exits_one_without_severity_exit_codes() {
+        let exit_code =
+            |severity, by_severity| ChecksFailed(exit_severity(severity, by_severity)).exit_code();
+        assert_eq!(exit_code(config::Severity::Critical, false), 1);
+        assert_eq!(exit_code(config::Severity::Warning, false), 1);
+        assert_eq!(exit_code(config::Severity::Critical, true), 2);
+        assert_eq!(exit_code(config::Severity::Warning, true), 1);
+    }
+
+    #[test]
+    fn reports_unknown_only_for_nagios() {
+        let outcome = report_unknown(OutputFormat::Nagios, Err(anyhow::anyhow!("no config")));
+        assert!(outcome.is_err_and(|e| e.is::<ChecksUnknown>() && e.to_string() == "no config"));
+
+        let outcome = report_unknown(OutputFormat::Prometheus, Err(anyhow::anyhow!("no config")));
+        assert!(outcome.is_err_and(|e| !e.is::<ChecksUnknown>()));
+
+        let outcome = report_unknown(
+            OutputFormat::Nagios,
+            Err(ChecksFailed(config::Severity::Warning).into()),
+        );
+        assert!(outcome.is_err_and(|e| e.is::<ChecksFailed>()));
+    }
+
+    #[test]
+    fn ignores_silenced_check_failures() {
+        let mut results = vec![CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "prefix/".to_string(),
+            error: true,
+            silenced: true,
+            ..CheckResult::default()
+        }];
+
+        assert_eq!(worst_failure(&results), None);
+
+        results.push(CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "other/".to_string(),
+            ..CheckResult::default()
+        });
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
+    }
+
+    #[test]
+    fn silences_global_and_per_check() {
+        let now = Utc::now();
+        let expired = config::Silence {
+            until: Some(now - chrono::Duration::hours(1)),
+            ..config::Silence::default()
+        };
+        let active = config::Silence {
+            until: Some(now + chrono::Duration::hours(1)),
+            ..config::Silence::default()
+        };
+        let file = config::Object {
+            prefix: "db/".to_string(),
+            ..config::Object::default()
+        };
+
+        assert!(!silenced(&[], "bucket", &file, now));
+        assert!(!silenced(
+            std::slice::from_ref(&expired),
+            "bucket",
+            &file,
+            now
+        ));
+        assert!(silenced(
+            &[expired.clone(), active.clone()],
+            "bucket",
+            &file,
+            now
+        ));
+
+        let file = config::Object {
+            silence: vec![active],
+            ..file
+        };
+        assert!(silenced(&[expired], "bucket", &file, now));
+    }
+
     #[test]
     fn detects_check_failures_for_missing_objects() {
         let results = vec![CheckResult {
@@ -300,9 +1889,10 @@ mod tests {
             exist: false,
             error: false,
             size_mismatch: false,
+            ..CheckResult::default()
         }];
 
-        assert!(has_check_failures(&results));
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
     }
 
     #[test]
@@ -314,9 +1904,10 @@ mod tests {
             exist: false,
             error: true,
             size_mismatch: false,
+            ..CheckResult::default()
         }];
 
-        assert!(has_check_failures(&results));
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
     }
 
     #[test]
@@ -328,9 +1919,10 @@ mod tests {
             exist: true,
             error: false,
             size_mismatch: true,
+            ..CheckResult::default()
         }];
 
-        assert!(has_check_failures(&results));
+        assert_eq!(worst_failure(&results), Some(config::Severity::Critical));
     }
 
     #[test]
@@ -342,9 +1934,10 @@ mod tests {
             exist: true,
             error: false,
             size_mismatch: false,
+            ..CheckResult::default()
         }];
 
-        assert!(!has_check_failures(&results));
+        assert_eq!(worst_failure(&results), None);
     }
 
     #[test]
@@ -356,6 +1949,7 @@ mod tests {
             exist: true,
             error: false,
             size_mismatch: false,
+            ..CheckResult::default()
         }];
         let out = format_prometheus(&results);
         assert!(out.contains(r#"s3mon_object_exists{bucket="cubeta",prefix="E"} 1"#));
@@ -372,8 +1966,44 @@ mod tests {
             exist: true,
             error: false,
             size_mismatch: false,
+            ..CheckResult::default()
         }];
         let out = format_influxdb(&results);
         assert!(out.contains("s3mon,bucket=cubeta,prefix=E error=0i,exist=1i,size_mismatch=0i"));
     }
+
+    #[tokio::test]
+    async fn execd_gathers_once_per_input_line() -> Result<()> {
+        let monitor = Arc::new(make_monitor_with(vec![
+            (200, SdkBody::from(list_body("E/backup.tar", 1024))),
+            (200, SdkBody::from(list_body("E/backup.tar", 1024))),
+        ]));
+        let yml: config::Config = serde_yaml::from_str(
+            "s3mon:\n  buckets:\n    cubeta:\n      - prefix: E\n        age: 86400\n",
+        )?;
+        let options = OutputOptions {
+            precision: Some(Precision::Seconds),
+            ..OutputOptions::default()
+        };
+
+        let mut out = Vec::new();
+        serve(
+            &monitor,
+            &yml,
+            OutputFormat::Influxdb,
+            &options,
+            &mut Tracker::new(History::new(None, true), &yml)?,
+            &b"\n\n"[..],
+            &mut out,
+        )
+        .await?;
+
+        let out = String::from_utf8(out)?;
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            assert!(line.starts_with("s3mon,bucket=cubeta,prefix=E error=0i,exist=1i"));
+        }
+        Ok(())
+    }
 }
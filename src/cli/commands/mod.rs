@@ -1,3 +1,4 @@
+use crate::output::DEFAULT_PATH_TEMPLATE;
 use clap::{
     Arg, ArgAction, ColorChoice, Command,
     builder::styling::{AnsiColor, Effects, Styles},
@@ -28,36 +29,209 @@ pub fn new() -> Command {
         .about(env!("CARGO_PKG_DESCRIPTION"))
         .color(ColorChoice::Auto)
         .styles(styles)
+        .subcommand_negates_reqs(true)
+        .arg(config_arg())
         .arg(
-            Arg::new("config")
-                .short('c')
-                .long("config")
-                .value_name("FILE")
-                .help("Path to configuration YAML file")
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Increase log verbosity (-v INFO, -vv DEBUG, -vvv TRACE)")
+                .action(ArgAction::Count)
+                .global(true),
+        )
+        .args(report_args())
+        .args(output_args())
+        .args(run_args())
+        .subcommand(list_command())
+        .subcommand(check_command())
+        .subcommand(init_command())
+        .subcommand(doctor_command())
+}
+
+fn config_arg() -> Arg {
+    Arg::new("config")
+        .short('c')
+        .long("config")
+        .value_name("FILE")
+        .help("Path to configuration YAML file")
+        .required(true)
+}
+
+/// `list`: the objects each check sees and why it passes or fails.
+fn list_command() -> Command {
+    Command::new("list")
+        .about("Show the objects each check sees and why it passes or fails")
+        .arg(config_arg())
+        .arg(
+            Arg::new("bucket")
+                .long("bucket")
+                .value_name("BUCKET")
+                .help("Only list the checks of BUCKET"),
+        )
+        .arg(
+            Arg::new("prefix")
+                .long("prefix")
+                .value_name("PREFIX")
+                .help("Only list the checks whose prefix starts with PREFIX"),
+        )
+}
+
+/// How results are printed and reported through the exit status.
+fn report_args() -> [Arg; 3] {
+    [
+        Arg::new("format")
+            .short('f')
+            .long("format")
+            .value_name("FORMAT")
+            .help(
+                "Output format: prometheus (default), influxdb, nagios, junit, table, graphite or statsd",
+            )
+            .default_value("prometheus")
+            .value_parser([
+                "prometheus",
+                "influxdb",
+                "nagios",
+                "junit",
+                "table",
+                "graphite",
+                "statsd",
+            ]),
+        Arg::new("exit-on-check-failure")
+            .long("exit-on-check-failure")
+            .help("Exit with status 1 if any check fails")
+            .action(ArgAction::SetTrue),
+        Arg::new("severity-exit-codes")
+            .long("severity-exit-codes")
+            .help("With --exit-on-check-failure, exit with status 2 if a critical check fails")
+            .action(ArgAction::SetTrue)
+            .requires("exit-on-check-failure"),
+    ]
+}
+
+/// Format-specific output settings and delivery.
+fn output_args() -> [Arg; 4] {
+    [
+        Arg::new("path-template")
+            .long("path-template")
+            .value_name("TEMPLATE")
+            .help("Metric path for graphite and statsd, using {bucket}, {prefix}, {suffix} and {subdir}")
+            .default_value(DEFAULT_PATH_TEMPLATE),
+        Arg::new("precision")
+            .long("precision")
+            .value_name("PRECISION")
+            .help("Append the run start time to influxdb lines in s, ms, us or ns")
+            .value_parser(["s", "ms", "us", "ns"]),
+        Arg::new("float-fields")
+            .long("float-fields")
+            .help("Add age and size float fields of the newest object to influxdb lines")
+            .action(ArgAction::SetTrue),
+        Arg::new("send")
+            .long("send")
+            .value_name("URL")
+            .help("Send the output to tcp://host:port or udp://host:port instead of stdout"),
+    ]
+}
+
+/// How often the checks run and what is kept between runs.
+fn run_args() -> [Arg; 3] {
+    [
+        Arg::new("execd")
+            .long("execd")
+            .help("Keep running and print fresh results for every line read from stdin (Telegraf execd)")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("exit-on-check-failure"),
+        Arg::new("interval")
+            .long("interval")
+            .value_name("DURATION")
+            .help("With --execd, run the checks every DURATION (e.g. 5m) instead of reading stdin")
+            .requires("execd"),
+        Arg::new("state-file")
+            .long("state-file")
+            .value_name("FILE")
+            .help("Keep per-check history between runs in FILE to detect state changes"),
+    ]
+}
+
+/// `check`: a single check defined on the command line instead of a config.
+fn check_command() -> Command {
+    Command::new("check")
+        .about("Run a single check given on the command line, without a config file")
+        .arg(
+            Arg::new("bucket")
+                .long("bucket")
+                .value_name("BUCKET")
+                .help("Bucket to check")
                 .required(true),
         )
         .arg(
-            Arg::new("format")
-                .short('f')
-                .long("format")
-                .value_name("FORMAT")
-                .help("Output format: prometheus (default) or influxdb")
-                .default_value("prometheus")
-                .value_parser(["prometheus", "influxdb"]),
+            Arg::new("prefix")
+                .long("prefix")
+                .value_name("PREFIX")
+                .help("Key prefix of the objects")
+                .required(true),
         )
         .arg(
-            Arg::new("verbose")
-                .short('v')
-                .long("verbose")
-                .help("Increase log verbosity (-v INFO, -vv DEBUG, -vvv TRACE)")
-                .action(ArgAction::Count),
+            Arg::new("suffix")
+                .long("suffix")
+                .value_name("SUFFIX")
+                .help("Only consider keys ending in SUFFIX, e.g. .gz"),
+        )
+        .arg(
+            Arg::new("age")
+                .long("age")
+                .value_name("AGE")
+                .help("Maximum age of the newest object, in seconds or e.g. 1d [default: 1d]"),
+        )
+        .arg(
+            Arg::new("size")
+                .long("size")
+                .value_name("SIZE")
+                .help("Minimum size of a fresh object, in bytes or e.g. 1MiB"),
+        )
+        .args(connection_args())
+        .args(report_args())
+        .args(output_args())
+}
+
+/// `init`: propose a config from the objects found in a bucket.
+fn init_command() -> Command {
+    Command::new("init")
+        .about("Propose a config with checks inferred from the objects in a bucket")
+        .arg(
+            Arg::new("bucket")
+                .long("bucket")
+                .value_name("BUCKET")
+                .help("Bucket to list")
+                .required(true),
         )
         .arg(
-            Arg::new("exit-on-check-failure")
-                .long("exit-on-check-failure")
-                .help("Exit with status 1 if any check is missing, errors, or size-mismatched")
-                .action(ArgAction::SetTrue),
+            Arg::new("prefix")
+                .long("prefix")
+                .value_name("PREFIX")
+                .help("Only list keys starting with PREFIX"),
         )
+        .args(connection_args())
+}
+
+/// `doctor`: how `s3mon` connects and whether it may list the buckets.
+fn doctor_command() -> Command {
+    Command::new("doctor")
+        .about("Report credentials, region, endpoint, clock skew and bucket permissions")
+        .arg(config_arg())
+}
+
+/// Connection settings for the subcommands that run without a config file.
+fn connection_args() -> [Arg; 2] {
+    [
+        Arg::new("endpoint")
+            .long("endpoint")
+            .value_name("URL")
+            .help("Custom S3 endpoint, e.g. https://minio.example.com:9000"),
+        Arg::new("region")
+            .long("region")
+            .value_name("REGION")
+            .help("Region, or signing label for custom endpoints"),
+    ]
 }
 
 #[cfg(test)]
@@ -120,5 +294,125 @@ mod tests {
             "--exit-on-check-failure",
         ]);
         assert!(matches.get_flag("exit-on-check-failure"));
+        assert!(!matches.get_flag("severity-exit-codes"));
+        assert!(
+            new()
+                .try_get_matches_from(vec!["s3mon", "-c", "example.yml", "--severity-exit-codes"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_execd_flags() {
+        let matches = new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--execd",
+            "--interval",
+            "5m",
+        ]);
+        assert!(matches.get_flag("execd"));
+        assert_eq!(
+            matches.get_one::<String>("interval").map(String::as_str),
+            Some("5m")
+        );
+        assert!(
+            new()
+                .try_get_matches_from(vec!["s3mon", "-c", "example.yml", "--interval", "5m"])
+                .is_err()
+        );
+        assert!(
+            new()
+                .try_get_matches_from(vec![
+                    "s3mon",
+                    "-c",
+                    "example.yml",
+                    "--execd",
+                    "--exit-on-check-failure",
+                ])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_list_subcommand() {
+        let matches = new().get_matches_from(vec![
+            "s3mon",
+            "-v",
+            "list",
+            "-c",
+            "example.yml",
+            "--bucket",
+            "backups",
+            "--prefix",
+            "db/",
+        ]);
+        assert_eq!(matches.get_count("verbose"), 1);
+        let list = matches.subcommand_matches("list");
+        assert_eq!(
+            list.and_then(|m| m.get_one::<String>("config"))
+                .map(String::as_str),
+            Some("example.yml")
+        );
+        assert_eq!(
+            list.and_then(|m| m.get_one::<String>("bucket"))
+                .map(String::as_str),
+            Some("backups")
+        );
+        assert!(new().try_get_matches_from(vec!["s3mon", "list"]).is_err());
+        assert!(new().try_get_matches_from(vec!["s3mon"]).is_err());
+    }
+
+    #[test]
+    fn test_check_subcommand() {
+        let matches = new().get_matches_from(vec![
+            "s3mon", "check", "--bucket", "backups", "--prefix", "db/", "--age", "1d", "-f",
+            "nagios",
+        ]);
+        let check = matches.subcommand_matches("check");
+        assert_eq!(
+            check
+                .and_then(|m| m.get_one::<String>("format"))
+                .map(String::as_str),
+            Some("nagios")
+        );
+        assert!(
+            new()
+                .try_get_matches_from(vec!["s3mon", "check", "--bucket", "backups"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_init_subcommand() {
+        let matches = new().get_matches_from(vec![
+            "s3mon",
+            "init",
+            "--bucket",
+            "backups",
+            "--endpoint",
+            "http://localhost:9000",
+        ]);
+        let init = matches.subcommand_matches("init");
+        assert_eq!(
+            init.and_then(|m| m.get_one::<String>("endpoint"))
+                .map(String::as_str),
+            Some("http://localhost:9000")
+        );
+        assert!(new().try_get_matches_from(vec!["s3mon", "init"]).is_err());
+    }
+
+    #[test]
+    fn test_doctor_subcommand() {
+        let matches = new().get_matches_from(vec!["s3mon", "doctor", "-c", "example.yml"]);
+        assert_eq!(
+            matches
+                .subcommand_matches("doctor")
+                .and_then(|m| m.get_one::<String>("config"))
+                .map(String::as_str),
+            Some("example.yml")
+        );
+        assert!(new().try_get_matches_from(vec!["s3mon", "doctor"]).is_err());
     }
 }
@@ -1,8 +1,12 @@
-use crate::cli::actions::Action;
-use crate::output::OutputFormat;
+use crate::cli::actions::{Action, OutputOptions};
+use crate::config::{Config, Data, Notify, Object};
+use crate::output::{DEFAULT_PATH_TEMPLATE, OutputFormat, Precision};
+use crate::send::Target;
 use anyhow::Result;
 use clap::ArgMatches;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Convert [`ArgMatches`] into a typed [`Action`].
 ///
@@ -10,6 +14,196 @@ use std::path::PathBuf;
 ///
 /// Returns an error if the config file path is missing or the file cannot be read.
 pub fn handler(matches: &ArgMatches) -> Result<Action> {
+    if let Some(("list", matches)) = matches.subcommand() {
+        return Ok(Action::List {
+            config: config_path(matches)?,
+            bucket: matches.get_one::<String>("bucket").cloned(),
+            prefix: matches.get_one::<String>("prefix").cloned(),
+        });
+    }
+
+    if let Some(("doctor", matches)) = matches.subcommand() {
+        return Ok(Action::Doctor {
+            config: config_path(matches)?,
+        });
+    }
+
+    if let Some(("check", matches)) = matches.subcommand() {
+        return Ok(Action::Check {
+            config: Box::new(check_config(matches)?),
+            format: format(matches),
+            exit_on_check_failure: matches.get_flag("exit-on-check-failure"),
+            severity_exit_codes: matches.get_flag("severity-exit-codes"),
+            options: output_options(matches)?,
+        });
+    }
+
+    if let Some(("init", matches)) = matches.subcommand() {
+        let arg = |name: &str| matches.get_one::<String>(name).cloned().unwrap_or_default();
+        return Ok(Action::Init {
+            config: Box::new(connection(matches, BTreeMap::new())),
+            bucket: arg("bucket"),
+            prefix: arg("prefix"),
+        });
+    }
+
+    let path = config_path(matches)?;
+    let format = format(matches);
+    let exit_on_check_failure = matches.get_flag("exit-on-check-failure");
+    let severity_exit_codes = matches.get_flag("severity-exit-codes");
+    let options = output_options(matches)?;
+
+    let state_file = matches.get_one::<String>("state-file").map(PathBuf::from);
+
+    if matches.get_flag("execd") {
+        let interval = matches
+            .get_one::<String>("interval")
+            .map(|text| parse_interval(text))
+            .transpose()?;
+
+        return Ok(Action::Execd {
+            config: path,
+            format,
+            interval,
+            state_file,
+            options,
+        });
+    }
+
+    Ok(Action::Monitor {
+        config: path,
+        format,
+        exit_on_check_failure,
+        severity_exit_codes,
+        state_file,
+        options,
+    })
+}
+
+fn format(matches: &ArgMatches) -> OutputFormat {
+    match matches.get_one::<String>("format").map(String::as_str) {
+        Some("influxdb") => OutputFormat::Influxdb,
+        Some("nagios") => OutputFormat::Nagios,
+        Some("junit") => OutputFormat::Junit,
+        Some("table") => OutputFormat::Table,
+        Some("graphite") => OutputFormat::Graphite,
+        Some("statsd") => OutputFormat::Statsd,
+        _ => OutputFormat::Prometheus,
+    }
+}
+
+fn output_options(matches: &ArgMatches) -> Result<OutputOptions> {
+    let path_template = matches
+        .get_one::<String>("path-template")
+        .map_or_else(|| DEFAULT_PATH_TEMPLATE.to_string(), String::clone);
+
+    let send = matches
+        .get_one::<String>("send")
+        .map(|target| target.parse::<Target>())
+        .transpose()?;
+
+    let precision = match matches.get_one::<String>("precision").map(String::as_str) {
+        Some("s") => Some(Precision::Seconds),
+        Some("ms") => Some(Precision::Milliseconds),
+        Some("us") => Some(Precision::Microseconds),
+        Some("ns") => Some(Precision::Nanoseconds),
+        _ => None,
+    };
+
+    Ok(OutputOptions {
+        path_template,
+        send,
+        precision,
+        float_fields: matches.get_flag("float-fields"),
+    })
+}
+
+/// A config holding the single check given to the `check` subcommand.
+fn check_config(matches: &ArgMatches) -> Result<Config> {
+    let arg = |name: &str| matches.get_one::<String>(name).cloned().unwrap_or_default();
+
+    let age = matches
+        .get_one::<String>("age")
+        .map(|text| parse_age(text))
+        .transpose()?;
+    let size = matches
+        .get_one::<String>("size")
+        .map(|text| parse_size(text))
+        .transpose()?;
+
+    let object = Object {
+        prefix: arg("prefix"),
+        suffix: arg("suffix"),
+        age: age.unwrap_or(Object::default().age),
+        size: size.unwrap_or_default(),
+        ..Object::default()
+    };
+
+    Ok(connection(
+        matches,
+        BTreeMap::from([(arg("bucket"), vec![object])]),
+    ))
+}
+
+/// A config with the `--endpoint` and `--region` of `matches` and `buckets`.
+///
+/// Credentials come from the AWS default chain, e.g. `AWS_ACCESS_KEY_ID`.
+fn connection(matches: &ArgMatches, buckets: BTreeMap<String, Vec<Object>>) -> Config {
+    let arg = |name: &str| matches.get_one::<String>(name).cloned().unwrap_or_default();
+    Config {
+        s3mon: Data {
+            endpoint: arg("endpoint"),
+            region: arg("region"),
+            access_key: String::new(),
+            secret_key: String::new(),
+            buckets,
+            bucket_checks: None,
+            silence: Vec::new(),
+            notify: Notify::default(),
+        },
+    }
+}
+
+/// Parse an `--age` given in seconds or as a duration such as `1d` or `36h`.
+fn parse_age(text: &str) -> Result<i64> {
+    if let Ok(seconds) = text.parse::<i64>() {
+        if seconds < 0 {
+            anyhow::bail!("invalid age '{text}': must not be negative");
+        }
+        return Ok(seconds);
+    }
+    let age = humantime::parse_duration(text)
+        .map_err(|e| anyhow::anyhow!("invalid age '{text}': {e}"))?;
+    Ok(i64::try_from(age.as_secs())?)
+}
+
+/// Parse a `--size` in bytes, optionally with a decimal (`KB`, `MB`, ...) or
+/// binary (`KiB`, `MiB`, ...) unit.
+fn parse_size(text: &str) -> Result<i64> {
+    let invalid = || anyhow::anyhow!("invalid size '{text}'");
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(digits);
+    let number: i64 = number.parse().map_err(|_| invalid())?;
+
+    let multiplier: i64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+    number.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// The `--config` file, which must be a regular file.
+fn config_path(matches: &ArgMatches) -> Result<PathBuf> {
     let config = matches
         .get_one::<String>("config")
         .ok_or_else(|| anyhow::anyhow!("--config is required"))?;
@@ -23,18 +217,17 @@ pub fn handler(matches: &ArgMatches) -> Result<Action> {
         anyhow::bail!("'{}' is not a regular file", path.display());
     }
 
-    let format = match matches.get_one::<String>("format").map(String::as_str) {
-        Some("influxdb") => OutputFormat::Influxdb,
-        _ => OutputFormat::Prometheus,
-    };
-
-    let exit_on_check_failure = matches.get_flag("exit-on-check-failure");
+    Ok(path)
+}
 
-    Ok(Action::Monitor {
-        config: path,
-        format,
-        exit_on_check_failure,
-    })
+/// Parse a non-zero `--interval` such as `30s` or `5m`.
+fn parse_interval(text: &str) -> Result<Duration> {
+    let interval = humantime::parse_duration(text)
+        .map_err(|e| anyhow::anyhow!("invalid interval '{text}': {e}"))?;
+    if interval.is_zero() {
+        anyhow::bail!("invalid interval '{text}': must be greater than zero");
+    }
+    Ok(interval)
 }
 
 #[cfg(test)]
@@ -51,11 +244,20 @@ mod tests {
             config,
             format,
             exit_on_check_failure,
+            severity_exit_codes,
+            state_file,
+            options,
         }) = action
         {
             assert_eq!(config, PathBuf::from("example.yml"));
             assert_eq!(format, OutputFormat::Prometheus);
             assert!(!exit_on_check_failure);
+            assert!(!severity_exit_codes);
+            assert_eq!(state_file, None);
+            assert_eq!(options.path_template, DEFAULT_PATH_TEMPLATE);
+            assert_eq!(options.send, None);
+            assert_eq!(options.precision, None);
+            assert!(!options.float_fields);
         }
     }
 
@@ -81,6 +283,111 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_handler_monitor_nagios_format() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--format",
+            "nagios",
+        ]);
+        let action = handler(&matches);
+        assert!(action.is_ok());
+        if let Ok(Action::Monitor { format, .. }) = action {
+            assert_eq!(format, OutputFormat::Nagios);
+        }
+    }
+
+    #[test]
+    fn test_handler_monitor_junit_format() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--format",
+            "junit",
+        ]);
+        let action = handler(&matches);
+        assert!(action.is_ok());
+        if let Ok(Action::Monitor { format, .. }) = action {
+            assert_eq!(format, OutputFormat::Junit);
+        }
+    }
+
+    #[test]
+    fn test_handler_monitor_table_format() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--format",
+            "table",
+        ]);
+        let action = handler(&matches);
+        assert!(action.is_ok());
+        if let Ok(Action::Monitor { format, .. }) = action {
+            assert_eq!(format, OutputFormat::Table);
+        }
+    }
+
+    #[test]
+    fn test_handler_monitor_graphite_send() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--format",
+            "graphite",
+            "--path-template",
+            "backups.{bucket}.{prefix}",
+            "--send",
+            "tcp://graphite:2003",
+        ]);
+        let action = handler(&matches);
+        assert!(action.is_ok());
+        if let Ok(Action::Monitor {
+            format, options, ..
+        }) = action
+        {
+            assert_eq!(format, OutputFormat::Graphite);
+            assert_eq!(options.path_template, "backups.{bucket}.{prefix}");
+            assert_eq!(options.send, Some(Target::Tcp("graphite:2003".to_string())));
+        }
+    }
+
+    #[test]
+    fn test_handler_monitor_influxdb_precision() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--format",
+            "influxdb",
+            "--precision",
+            "ms",
+            "--float-fields",
+        ]);
+        let action = handler(&matches);
+        assert!(action.is_ok());
+        if let Ok(Action::Monitor { options, .. }) = action {
+            assert_eq!(options.precision, Some(Precision::Milliseconds));
+            assert!(options.float_fields);
+        }
+    }
+
+    #[test]
+    fn test_handler_invalid_send_target() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--send",
+            "graphite:2003",
+        ]);
+        assert!(handler(&matches).is_err());
+    }
+
     #[test]
     fn test_handler_monitor_exit_on_check_failure() {
         let matches = commands::new().get_matches_from(vec![
@@ -88,15 +395,18 @@ mod tests {
             "-c",
             "example.yml",
             "--exit-on-check-failure",
+            "--severity-exit-codes",
         ]);
         let action = handler(&matches);
         assert!(action.is_ok());
         if let Ok(Action::Monitor {
             exit_on_check_failure,
+            severity_exit_codes,
             ..
         }) = action
         {
             assert!(exit_on_check_failure);
+            assert!(severity_exit_codes);
         }
     }
 
@@ -107,4 +417,198 @@ mod tests {
         let result = handler(&matches);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_handler_execd() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--format",
+            "influxdb",
+            "--execd",
+        ]);
+        let action = handler(&matches);
+        assert!(matches!(
+            action,
+            Ok(Action::Execd {
+                format: OutputFormat::Influxdb,
+                interval: None,
+                ..
+            })
+        ));
+
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--execd",
+            "--interval",
+            "5m",
+        ]);
+        if let Ok(Action::Execd { interval, .. }) = handler(&matches) {
+            assert_eq!(interval, Some(Duration::from_mins(5)));
+        } else {
+            unreachable!("expected an execd action");
+        }
+    }
+
+    #[test]
+    fn test_handler_invalid_interval() {
+        for interval in ["0s", "soon"] {
+            let matches = commands::new().get_matches_from(vec![
+                "s3mon",
+                "-c",
+                "example.yml",
+                "--execd",
+                "--interval",
+                interval,
+            ]);
+            assert!(handler(&matches).is_err());
+        }
+    }
+
+    #[test]
+    fn test_handler_state_file() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "-c",
+            "example.yml",
+            "--state-file",
+            "/var/lib/s3mon/state.json",
+        ]);
+        let action = handler(&matches);
+        assert!(action.is_ok());
+        if let Ok(Action::Monitor { state_file, .. }) = action {
+            assert_eq!(state_file, Some(PathBuf::from("/var/lib/s3mon/state.json")));
+        }
+    }
+
+    #[test]
+    fn test_handler_list() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "list",
+            "-c",
+            "example.yml",
+            "--prefix",
+            "db/",
+        ]);
+        if let Ok(Action::List {
+            config,
+            bucket,
+            prefix,
+        }) = handler(&matches)
+        {
+            assert_eq!(config, PathBuf::from("example.yml"));
+            assert_eq!(bucket, None);
+            assert_eq!(prefix.as_deref(), Some("db/"));
+        } else {
+            unreachable!("expected a list action");
+        }
+
+        let matches =
+            commands::new().get_matches_from(vec!["s3mon", "list", "-c", "/nonexistent.yml"]);
+        assert!(handler(&matches).is_err());
+    }
+
+    #[test]
+    fn test_handler_check() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "check",
+            "--bucket",
+            "backups",
+            "--prefix",
+            "db/",
+            "--suffix",
+            ".gz",
+            "--age",
+            "36h",
+            "--size",
+            "1MiB",
+            "--endpoint",
+            "http://localhost:9000",
+            "--exit-on-check-failure",
+        ]);
+        let Ok(Action::Check {
+            config,
+            format,
+            exit_on_check_failure,
+            ..
+        }) = handler(&matches)
+        else {
+            unreachable!("expected a check action");
+        };
+        assert_eq!(format, OutputFormat::Prometheus);
+        assert!(exit_on_check_failure);
+        assert_eq!(config.s3mon.endpoint, "http://localhost:9000");
+        let objects = config.s3mon.buckets.get("backups");
+        assert_eq!(objects.map(Vec::len), Some(1));
+        let object = objects.and_then(|objects| objects.first());
+        assert_eq!(object.map(|o| o.prefix.as_str()), Some("db/"));
+        assert_eq!(object.map(|o| o.suffix.as_str()), Some(".gz"));
+        assert_eq!(object.map(|o| o.age), Some(129_600));
+        assert_eq!(object.map(|o| o.size), Some(1_048_576));
+
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon", "check", "--bucket", "backups", "--prefix", "db/",
+        ]);
+        if let Ok(Action::Check { config, .. }) = handler(&matches) {
+            let object = config.s3mon.buckets.get("backups").and_then(|o| o.first());
+            assert_eq!(object.map(|o| (o.age, o.size)), Some((86_400, 0)));
+        } else {
+            unreachable!("expected a check action");
+        }
+    }
+
+    #[test]
+    fn test_parse_age_and_size() {
+        assert_eq!(parse_age("3600").ok(), Some(3_600));
+        assert_eq!(parse_age("1d").ok(), Some(86_400));
+        assert!(parse_age("yesterday").is_err());
+        assert!(parse_age("-60").is_err());
+
+        assert_eq!(parse_size("512").ok(), Some(512));
+        assert_eq!(parse_size("10KB").ok(), Some(10_000));
+        assert_eq!(parse_size("1 MiB").ok(), Some(1_048_576));
+        assert_eq!(parse_size("2g").ok(), Some(2 << 30));
+        assert!(parse_size("1.5MiB").is_err());
+        assert!(parse_size("MiB").is_err());
+        assert!(parse_size("10 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_handler_init() {
+        let matches = commands::new().get_matches_from(vec![
+            "s3mon",
+            "init",
+            "--bucket",
+            "backups",
+            "--region",
+            "eu-central-1",
+        ]);
+        let Ok(Action::Init {
+            config,
+            bucket,
+            prefix,
+        }) = handler(&matches)
+        else {
+            unreachable!("expected an init action");
+        };
+        assert_eq!(bucket, "backups");
+        assert_eq!(prefix, "");
+        assert_eq!(config.s3mon.region, "eu-central-1");
+        assert!(config.s3mon.buckets.is_empty());
+    }
+
+    #[test]
+    fn test_handler_doctor() {
+        let matches =
+            commands::new().get_matches_from(vec!["s3mon", "doctor", "-c", "example.yml"]);
+        let Ok(Action::Doctor { config }) = handler(&matches) else {
+            unreachable!("expected a doctor action");
+        };
+        assert_eq!(config, PathBuf::from("example.yml"));
+    }
 }
@@ -1,5 +1,6 @@
 use crate::cli::{actions::Action, commands, dispatch, telemetry};
 use anyhow::Result;
+use clap::ColorChoice;
 
 const fn get_verbosity_level(verbose_count: u8) -> Option<tracing::Level> {
     match verbose_count {
@@ -16,7 +17,14 @@ const fn get_verbosity_level(verbose_count: u8) -> Option<tracing::Level> {
 ///
 /// Returns an error if argument parsing, telemetry init, or dispatch fails.
 pub fn start() -> Result<Action> {
-    let matches = commands::new().get_matches();
+    let command = commands::new();
+    // Colored output (e.g. `--format table`) follows the same choice as the help text.
+    anstream::ColorChoice::write_global(match command.get_color() {
+        ColorChoice::Always => anstream::ColorChoice::Always,
+        ColorChoice::Never => anstream::ColorChoice::Never,
+        ColorChoice::Auto => anstream::ColorChoice::Auto,
+    });
+    let matches = command.get_matches();
     let verbosity_level = get_verbosity_level(matches.get_count("verbose"));
     telemetry::init(verbosity_level)?;
     let action = dispatch::handler(&matches)?;
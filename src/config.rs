@@ -1,12 +1,13 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
 use serde::Deserialize;
 use std::collections::BTreeMap;
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct Config {
     pub s3mon: Data,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct Data {
     #[serde(default)]
     pub endpoint: String,
@@ -17,9 +18,74 @@ pub struct Data {
     #[serde(default)]
     pub secret_key: String,
     pub buckets: BTreeMap<String, Vec<Object>>,
+    /// Bucket-level posture checks applied to every bucket in `buckets`.
+    #[serde(default)]
+    pub bucket_checks: Option<BucketChecks>,
+    /// Silences applied to every check.
+    #[serde(default)]
+    pub silence: Vec<Silence>,
+    /// Where to send alerts when checks start or stop failing.
+    #[serde(default)]
+    pub notify: Notify,
+}
+
+/// Opt-in bucket-level posture checks.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+pub struct BucketChecks {
+    #[serde(default)]
+    pub versioning: bool,
+    #[serde(default)]
+    pub public_access_block: bool,
+    #[serde(default)]
+    pub encryption: bool,
+    #[serde(default)]
+    pub lifecycle: bool,
+    #[serde(default)]
+    pub replication: bool,
+}
+
+impl BucketChecks {
+    /// The posture checks that are enabled, in a stable order.
+    #[must_use]
+    pub fn enabled(&self) -> Vec<Posture> {
+        [
+            (self.versioning, Posture::Versioning),
+            (self.public_access_block, Posture::PublicAccessBlock),
+            (self.encryption, Posture::Encryption),
+            (self.lifecycle, Posture::Lifecycle),
+            (self.replication, Posture::Replication),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, posture)| enabled.then_some(posture))
+        .collect()
+    }
+}
+
+/// A single bucket-level posture check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Posture {
+    Versioning,
+    PublicAccessBlock,
+    Encryption,
+    Lifecycle,
+    Replication,
+}
+
+impl Posture {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Versioning => "versioning",
+            Self::PublicAccessBlock => "public_access_block",
+            Self::Encryption => "encryption",
+            Self::Lifecycle => "lifecycle",
+            Self::Replication => "replication",
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct Object {
     pub prefix: String,
     #[serde(default)]
@@ -28,16 +94,481 @@ pub struct Object {
     pub age: i64,
     #[serde(default)]
     pub size: i64,
+    /// Flag objects older than this many seconds (`0` disables the check).
+    #[serde(default)]
+    pub max_object_age: i64,
+    #[serde(default)]
+    pub content: Option<Content>,
+    #[serde(default)]
+    pub checksum: Option<Checksum>,
+    /// Storage classes the newest matching object may use; empty disables the rule.
+    #[serde(default)]
+    pub storage_class: Vec<String>,
+    /// Required server-side encryption of the newest matching object, e.g. `aws:kms`.
+    #[serde(default)]
+    pub encryption: String,
+    #[serde(default)]
+    pub retention: Option<Retention>,
+    /// Run the check once per child "directory" found under `prefix`.
+    #[serde(default)]
+    pub per_subdirectory: bool,
+    /// Path appended to each discovered child, e.g. `backups/`.
+    #[serde(default)]
+    pub subpath: String,
+    /// Maximum number of children checked, to bound series cardinality.
+    #[serde(default = "default_max_subdirectories")]
+    pub max_subdirectories: usize,
+    /// Cron expression (UTC) of the job producing the objects, e.g. `0 2 * * 1-5`.
+    /// When set, it replaces `age`: the newest object must be newer than the
+    /// most recent expected run.
+    #[serde(default)]
+    pub schedule: String,
+    /// Time allowed after a scheduled run before its object is expected, in
+    /// seconds or as a duration such as `2h`.
+    #[serde(default, deserialize_with = "deserialize_seconds")]
+    pub grace: i64,
+    /// Silences applied to this check only.
+    #[serde(default)]
+    pub silence: Vec<Silence>,
+    /// How urgent a failure of this check is; decides the exit code.
+    #[serde(default)]
+    pub severity: Severity,
+    /// Consecutive failing runs before the check is reported as failing.
+    #[serde(default = "default_streak")]
+    pub fail_after: u32,
+    /// Consecutive passing runs before a failing check is reported as recovered.
+    #[serde(default = "default_streak")]
+    pub recover_after: u32,
+}
+
+impl Default for Object {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            suffix: String::new(),
+            age: default_age(),
+            size: 0,
+            max_object_age: 0,
+            content: None,
+            checksum: None,
+            storage_class: Vec::new(),
+            encryption: String::new(),
+            retention: None,
+            per_subdirectory: false,
+            subpath: String::new(),
+            max_subdirectories: default_max_subdirectories(),
+            schedule: String::new(),
+            grace: 0,
+            silence: Vec::new(),
+            severity: Severity::default(),
+            fail_after: default_streak(),
+            recover_after: default_streak(),
+        }
+    }
+}
+
+/// Content validation applied to the newest matching object.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Content {
+    pub format: ContentFormat,
+    /// Trailing bytes the object must end with, used by [`ContentFormat::Marker`].
+    #[serde(default)]
+    pub marker: String,
+    /// Maximum number of bytes downloaded; larger objects are validated up to this limit.
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    Gzip,
+    Zstd,
+    Json,
+    Ndjson,
+    Tar,
+    Marker,
+}
+
+/// Verification of the newest matching object against a checksum sidecar file.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Checksum {
+    /// Extension appended to the object key to find the sidecar, e.g. `dump.sql.gz.sha256`.
+    #[serde(default = "default_sidecar")]
+    pub sidecar: String,
+    #[serde(default)]
+    pub mode: ChecksumMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumMode {
+    /// Compare against the `ChecksumSHA256` object attribute, streaming the
+    /// object only when S3 has no full-object SHA-256 checksum.
+    #[default]
+    Attributes,
+    /// Always download the object and compute the digest.
+    Stream,
+}
+
+/// Severity of a failing check, ordered from least to most urgent.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Reported in the metrics only; never changes the exit code.
+    Info,
+    Warning,
+    #[default]
+    Critical,
+}
+
+impl Severity {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// Object Lock requirements for the newest matching object.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Retention {
+    /// Required retention mode, `COMPLIANCE` or `GOVERNANCE`.
+    #[serde(default = "default_retention_mode")]
+    pub mode: String,
+    /// Minimum number of days the retention must still be in effect.
+    #[serde(default)]
+    pub min_days: i64,
+    /// Require a legal hold to be set.
+    #[serde(default)]
+    pub legal_hold: bool,
+}
+
+/// Alert destinations notified when a check starts or stops failing.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct Notify {
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    #[serde(default)]
+    pub email: Option<Email>,
+    #[serde(default)]
+    pub events: Vec<EventsApi>,
+}
+
+/// Incidents opened and resolved through a `PagerDuty` Events API v2
+/// compatible endpoint, such as `PagerDuty` itself or `Opsgenie`.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct EventsApi {
+    /// Integration key of the service the incidents are opened on.
+    pub routing_key: String,
+    #[serde(default = "default_events_url")]
+    pub url: String,
+    /// Least severe checks that open incidents.
+    #[serde(default)]
+    pub severity: Severity,
+    /// Reported as the `source` of the incidents.
+    #[serde(default = "default_source")]
+    pub source: String,
+    /// Additional attempts after a failed delivery.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further attempt.
+    #[serde(
+        default = "default_retry_delay",
+        deserialize_with = "deserialize_positive_seconds"
+    )]
+    pub retry_delay: i64,
+    /// Timeout of a single attempt.
+    #[serde(
+        default = "default_timeout",
+        deserialize_with = "deserialize_positive_seconds"
+    )]
+    pub timeout: i64,
+}
+
+/// Summary mails sent over SMTP when checks fail.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Email {
+    pub host: String,
+    /// Defaults to 587 for `starttls`, 465 for `tls` and 25 for `none`.
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: EmailTls,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Subject with `{status}`, `{failed}` and `{total}` placeholders.
+    #[serde(default = "default_subject")]
+    pub subject: String,
+    /// Also send a mail when failing checks recover.
+    #[serde(default)]
+    pub recovery: bool,
+    /// Timeout of the SMTP session.
+    #[serde(
+        default = "default_timeout",
+        deserialize_with = "deserialize_positive_seconds"
+    )]
+    pub timeout: i64,
+}
+
+impl Email {
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.tls {
+            EmailTls::Starttls => 587,
+            EmailTls::Tls => 465,
+            EmailTls::None => 25,
+        })
+    }
+}
+
+/// Transport security of the SMTP connection.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTls {
+    /// Upgrade a plain connection with `STARTTLS`, which must be offered.
+    #[default]
+    Starttls,
+    /// Implicit TLS from the start of the connection.
+    Tls,
+    /// Unencrypted, e.g. for a local relay.
+    None,
+}
+
+/// An HTTP endpoint that receives a JSON `POST` per state change.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub template: WebhookTemplate,
+    /// Additional attempts after a failed delivery.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further attempt.
+    #[serde(
+        default = "default_retry_delay",
+        deserialize_with = "deserialize_positive_seconds"
+    )]
+    pub retry_delay: i64,
+    /// Timeout of a single attempt.
+    #[serde(
+        default = "default_timeout",
+        deserialize_with = "deserialize_positive_seconds"
+    )]
+    pub timeout: i64,
+}
+
+/// Body layout of a webhook request.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookTemplate {
+    /// Plain JSON object with one field per attribute of the change.
+    #[default]
+    Generic,
+    /// Slack incoming webhook message.
+    Slack,
+    /// Microsoft Teams incoming webhook message card.
+    Teams,
+}
+
+/// A window during which failing checks do not fail the run.
+///
+/// Every condition that is set must hold for the silence to be active; an
+/// entry with only `until` silences the check until that date.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct Silence {
+    /// Start of the silence, as RFC 3339 or `YYYY-MM-DD` (UTC).
+    #[serde(default, deserialize_with = "deserialize_datetime")]
+    pub start: Option<DateTime<Utc>>,
+    /// Expiry of the silence, as RFC 3339 or `YYYY-MM-DD` (UTC).
+    #[serde(default, deserialize_with = "deserialize_datetime")]
+    pub until: Option<DateTime<Utc>>,
+    /// Days of the week (UTC) the silence applies to, e.g. `[sat, sun]`.
+    #[serde(default, deserialize_with = "deserialize_weekdays")]
+    pub weekdays: Vec<Weekday>,
+    /// Daily time range (UTC) such as `22:00-06:00`; may wrap past midnight.
+    #[serde(default, deserialize_with = "deserialize_time_range")]
+    pub time: Option<(NaiveTime, NaiveTime)>,
+    /// Why the check is silenced, for the operator's benefit.
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl Silence {
+    /// Whether the silence is in effect at `now`.
+    #[must_use]
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        if self.start.is_some_and(|start| now < start)
+            || self.until.is_some_and(|until| now >= until)
+        {
+            return false;
+        }
+        if !self.weekdays.is_empty() && !self.weekdays.contains(&now.weekday()) {
+            return false;
+        }
+        self.time.is_none_or(|(from, to)| {
+            let time = now.time();
+            if from <= to {
+                from <= time && time < to
+            } else {
+                time >= from || time < to
+            }
+        })
+    }
+}
+
+fn deserialize_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&text)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+                .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        })
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("invalid date '{text}'")))
+}
+
+fn deserialize_weekdays<'de, D>(deserializer: D) -> Result<Vec<Weekday>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|day| {
+            day.parse()
+                .map_err(|_| serde::de::Error::custom(format!("invalid weekday '{day}'")))
+        })
+        .collect()
+}
+
+fn deserialize_time_range<'de, D>(
+    deserializer: D,
+) -> Result<Option<(NaiveTime, NaiveTime)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    text.split_once('-')
+        .and_then(|(from, to)| {
+            let from = NaiveTime::parse_from_str(from.trim(), "%H:%M").ok()?;
+            let to = NaiveTime::parse_from_str(to.trim(), "%H:%M").ok()?;
+            Some(Some((from, to)))
+        })
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid time range '{text}'")))
+}
+
+/// Deserialize a duration given either as seconds or as a human readable
+/// string such as `90m` or `2h 30m`; negative durations are rejected.
+fn deserialize_seconds<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(i64),
+        Text(String),
+    }
+
+    match Seconds::deserialize(deserializer)? {
+        Seconds::Number(seconds) if seconds < 0 => Err(serde::de::Error::custom(format!(
+            "invalid duration {seconds}: must not be negative"
+        ))),
+        Seconds::Number(seconds) => Ok(seconds),
+        Seconds::Text(text) => humantime::parse_duration(&text)
+            .map_err(serde::de::Error::custom)
+            .and_then(|d| i64::try_from(d.as_secs()).map_err(serde::de::Error::custom)),
+    }
+}
+
+/// Deserialize a duration like [`deserialize_seconds`], rejecting zero too,
+/// for timeouts and retry delays.
+fn deserialize_positive_seconds<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match deserialize_seconds(deserializer)? {
+        0 => Err(serde::de::Error::custom(
+            "invalid duration 0: must be positive",
+        )),
+        seconds => Ok(seconds),
+    }
 }
 
 const fn default_age() -> i64 {
     86400
 }
 
+const fn default_streak() -> u32 {
+    1
+}
+
+const fn default_retries() -> u32 {
+    3
+}
+
+const fn default_retry_delay() -> i64 {
+    1
+}
+
+const fn default_timeout() -> i64 {
+    10
+}
+
+fn default_events_url() -> String {
+    "https://events.pagerduty.com/v2/enqueue".to_string()
+}
+
+fn default_source() -> String {
+    "s3mon".to_string()
+}
+
+fn default_subject() -> String {
+    "[s3mon] {status}: {failed} of {total} checks failing".to_string()
+}
+
+const fn default_max_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
+const fn default_max_subdirectories() -> usize {
+    100
+}
+
+fn default_sidecar() -> String {
+    ".sha256".to_string()
+}
+
+fn default_retention_mode() -> String {
+    "COMPLIANCE".to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// The checks of the `backups` bucket in `yml`.
+    fn checks(yml: &str) -> Result<Vec<Object>, serde_yaml::Error> {
+        let parsed: Config = serde_yaml::from_str(yml)?;
+        Ok(parsed
+            .s3mon
+            .buckets
+            .get("backups")
+            .cloned()
+            .unwrap_or_default())
+    }
+
     #[test]
     fn test_parse_config() -> Result<(), serde_yaml::Error> {
         let yml = r"
@@ -65,18 +596,21 @@ s3mon:
                     suffix: String::new(),
                     age: 43200,
                     size: 0,
+                    ..Object::default()
                 },
                 Object {
                     prefix: "bar".to_string(),
                     suffix: String::new(),
                     age: 86400,
                     size: 0,
+                    ..Object::default()
                 },
                 Object {
                     prefix: "baz".to_string(),
                     suffix: ".log".to_string(),
                     age: 86400,
                     size: 1024,
+                    ..Object::default()
                 },
             ],
         );
@@ -87,10 +621,439 @@ s3mon:
                 access_key: "ACCESS_KEY_ID".to_string(),
                 secret_key: "SECRET_ACCESS_KEY".to_string(),
                 buckets,
+                bucket_checks: None,
+                notify: Notify::default(),
+                silence: Vec::new(),
             },
         };
         let parsed: Config = serde_yaml::from_str(yml)?;
         assert_eq!(expected, parsed);
         Ok(())
     }
+
+    #[test]
+    fn test_parse_content() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+        content:
+          format: gzip
+      - prefix: db/
+        content:
+          format: marker
+          marker: '-- Dump completed'
+          max_bytes: 1024
+        ";
+        let objects = checks(yml)?;
+        assert_eq!(
+            objects.first().and_then(|o| o.content.clone()),
+            Some(Content {
+                format: ContentFormat::Gzip,
+                marker: String::new(),
+                max_bytes: 16 * 1024 * 1024,
+            })
+        );
+        assert_eq!(
+            objects.get(1).and_then(|o| o.content.clone()),
+            Some(Content {
+                format: ContentFormat::Marker,
+                marker: "-- Dump completed".to_string(),
+                max_bytes: 1024,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_checksum() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+        checksum: {}
+      - prefix: db/
+        checksum:
+          sidecar: .sum
+          mode: stream
+        ";
+        let objects = checks(yml)?;
+        assert_eq!(
+            objects.first().and_then(|o| o.checksum.clone()),
+            Some(Checksum {
+                sidecar: ".sha256".to_string(),
+                mode: ChecksumMode::Attributes,
+            })
+        );
+        assert_eq!(
+            objects.get(1).and_then(|o| o.checksum.clone()),
+            Some(Checksum {
+                sidecar: ".sum".to_string(),
+                mode: ChecksumMode::Stream,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_compliance() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+        storage_class: [GLACIER_IR, STANDARD_IA]
+        encryption: aws:kms
+        ";
+        let object = checks(yml)?.first().cloned().unwrap_or_default();
+        assert_eq!(object.storage_class, vec!["GLACIER_IR", "STANDARD_IA"]);
+        assert_eq!(object.encryption, "aws:kms");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_retention() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+        retention:
+          min_days: 30
+      - prefix: db/
+        retention:
+          mode: GOVERNANCE
+          legal_hold: true
+        ";
+        let objects = checks(yml)?;
+        assert_eq!(
+            objects.first().and_then(|o| o.retention.clone()),
+            Some(Retention {
+                mode: "COMPLIANCE".to_string(),
+                min_days: 30,
+                legal_hold: false,
+            })
+        );
+        assert_eq!(
+            objects.get(1).and_then(|o| o.retention.clone()),
+            Some(Retention {
+                mode: "GOVERNANCE".to_string(),
+                min_days: 0,
+                legal_hold: true,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_bucket_checks() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  bucket_checks:
+    versioning: true
+    lifecycle: true
+  buckets:
+    backups:
+      - prefix: db/
+        ";
+        let parsed: Config = serde_yaml::from_str(yml)?;
+        assert_eq!(
+            parsed.s3mon.bucket_checks.map(|c| c.enabled()),
+            Some(vec![Posture::Versioning, Posture::Lifecycle])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_per_subdirectory() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: tenants/
+        per_subdirectory: true
+        subpath: backups/
+      - prefix: db/
+        ";
+        let objects = checks(yml)?;
+        let tenants = objects.first().cloned().unwrap_or_default();
+        assert!(tenants.per_subdirectory);
+        assert_eq!(tenants.subpath, "backups/");
+        assert_eq!(tenants.max_subdirectories, 100);
+        assert!(!objects.get(1).is_some_and(|o| o.per_subdirectory));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_max_object_age() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+        max_object_age: 2592000
+        ";
+        let object = checks(yml)?.first().cloned().unwrap_or_default();
+        assert_eq!(object.max_object_age, 2_592_000);
+        assert_eq!(Object::default().max_object_age, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_schedule() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+        schedule: 0 2 * * 1-5
+        grace: 2h
+      - prefix: logs/
+        schedule: 0 * * * *
+        grace: 900
+        ";
+        let objects = checks(yml)?;
+        let db = objects.first().cloned().unwrap_or_default();
+        assert_eq!(db.schedule, "0 2 * * 1-5");
+        assert_eq!(db.grace, 7200);
+        let logs = objects.get(1).cloned().unwrap_or_default();
+        assert_eq!(logs.grace, 900);
+        assert!(Object::default().schedule.is_empty());
+
+        let invalid = "s3mon:\n  buckets:\n    b:\n      - prefix: x\n        grace: soon\n";
+        assert!(serde_yaml::from_str::<Config>(invalid).is_err());
+        let negative = "s3mon:\n  buckets:\n    b:\n      - prefix: x\n        grace: -60\n";
+        assert!(serde_yaml::from_str::<Config>(negative).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_silence() -> Result<(), serde_yaml::Error> {
+        let yml = r#"
+---
+s3mon:
+  silence:
+    - start: 2026-10-20T00:00:00Z
+      until: 2026-10-21
+      reason: migration
+  buckets:
+    backups:
+      - prefix: db/
+        silence:
+          - weekdays: [sat, Sunday]
+            time: "22:00-06:00"
+        "#;
+        let parsed: Config = serde_yaml::from_str(yml)?;
+        let global = parsed.s3mon.silence.first().cloned().unwrap_or_default();
+        assert_eq!(global.reason, "migration");
+        let at = |t: &str| {
+            DateTime::parse_from_rfc3339(t)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_default()
+        };
+        assert!(!global.is_active(at("2026-10-19T23:59:59Z")));
+        assert!(global.is_active(at("2026-10-20T12:00:00Z")));
+        assert!(!global.is_active(at("2026-10-21T00:00:00Z")));
+
+        let weekend = checks(yml)?
+            .first()
+            .and_then(|object| object.silence.first())
+            .cloned()
+            .unwrap_or_default();
+        assert_eq!(weekend.weekdays, vec![Weekday::Sat, Weekday::Sun]);
+        // 2026-10-24 is a Saturday.
+        assert!(weekend.is_active(at("2026-10-24T23:00:00Z")));
+        assert!(weekend.is_active(at("2026-10-24T05:59:00Z")));
+        assert!(!weekend.is_active(at("2026-10-24T12:00:00Z")));
+        assert!(!weekend.is_active(at("2026-10-23T23:00:00Z")));
+
+        assert!(Silence::default().is_active(Utc::now()));
+
+        for invalid in ["until: tomorrow", "weekdays: [funday]", "time: 22-06"] {
+            let yml = format!("s3mon:\n  silence:\n    - {invalid}\n  buckets: {{}}\n");
+            assert!(serde_yaml::from_str::<Config>(&yml).is_err(), "{invalid}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_severity() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+      - prefix: logs/
+        severity: warning
+      - prefix: tmp/
+        severity: info
+        ";
+        let severities: Vec<Severity> = checks(yml)?.iter().map(|o| o.severity).collect();
+        assert_eq!(
+            severities,
+            vec![Severity::Critical, Severity::Warning, Severity::Info]
+        );
+        assert!(Severity::Info < Severity::Warning && Severity::Warning < Severity::Critical);
+        assert!(serde_yaml::from_str::<Severity>("fatal").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_notify() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  notify:
+    webhooks:
+      - url: https://hooks.example.com/s3mon
+      - url: https://hooks.slack.com/services/T000/B000/XXX
+        template: slack
+        retries: 5
+        retry_delay: 30s
+        timeout: 1m
+  buckets:
+    backups:
+      - prefix: db/
+        ";
+        let parsed: Config = serde_yaml::from_str(yml)?;
+        let webhooks = parsed.s3mon.notify.webhooks;
+        assert_eq!(
+            webhooks.first(),
+            Some(&Webhook {
+                url: "https://hooks.example.com/s3mon".to_string(),
+                template: WebhookTemplate::Generic,
+                retries: 3,
+                retry_delay: 1,
+                timeout: 10,
+            })
+        );
+        assert_eq!(
+            webhooks.get(1),
+            Some(&Webhook {
+                url: "https://hooks.slack.com/services/T000/B000/XXX".to_string(),
+                template: WebhookTemplate::Slack,
+                retries: 5,
+                retry_delay: 30,
+                timeout: 60,
+            })
+        );
+        assert!(serde_yaml::from_str::<WebhookTemplate>("discord").is_err());
+
+        for field in ["timeout: 0", "retry_delay: 0s", "timeout: -5"] {
+            let invalid = format!("url: https://hooks.example.com/s3mon\n{field}\n");
+            assert!(serde_yaml::from_str::<Webhook>(&invalid).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_debounce() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  buckets:
+    backups:
+      - prefix: db/
+      - prefix: replica/
+        fail_after: 3
+        recover_after: 2
+        ";
+        let streaks: Vec<(u32, u32)> = checks(yml)?
+            .iter()
+            .map(|o| (o.fail_after, o.recover_after))
+            .collect();
+        assert_eq!(streaks, vec![(1, 1), (3, 2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_email() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  notify:
+    email:
+      host: smtp.example.com
+      username: s3mon
+      password: secret
+      from: s3mon@example.com
+      to: [ops@example.com, backup@example.com]
+      recovery: true
+  buckets:
+    backups:
+      - prefix: db/
+        ";
+        let parsed: Config = serde_yaml::from_str(yml)?;
+        let email = parsed.s3mon.notify.email.unwrap_or_else(|| unreachable!());
+        assert_eq!(email.tls, EmailTls::Starttls);
+        assert_eq!(email.port(), 587);
+        assert_eq!(email.to.len(), 2);
+        assert!(email.recovery);
+        assert_eq!(email.timeout, 10);
+        assert!(email.subject.contains("{failed} of {total}"));
+
+        let email: Email =
+            serde_yaml::from_str("host: relay\ntls: none\nfrom: a@b.c\nto: [d@e.f]\n")?;
+        assert_eq!(email.port(), 25);
+        assert!(!email.recovery);
+        let invalid = "host: relay\nfrom: a@b.c\nto: [d@e.f]\ntimeout: 0\n";
+        assert!(serde_yaml::from_str::<Email>(invalid).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_events() -> Result<(), serde_yaml::Error> {
+        let yml = r"
+---
+s3mon:
+  notify:
+    events:
+      - routing_key: R0UT1NGK3Y
+      - routing_key: 0PSG3N13
+        url: http://127.0.0.1:8080/v2/enqueue
+        severity: warning
+        source: backup-host
+        retries: 0
+  buckets:
+    backups:
+      - prefix: db/
+        ";
+        let parsed: Config = serde_yaml::from_str(yml)?;
+        let events = parsed.s3mon.notify.events;
+        assert_eq!(
+            events.first(),
+            Some(&EventsApi {
+                routing_key: "R0UT1NGK3Y".to_string(),
+                url: "https://events.pagerduty.com/v2/enqueue".to_string(),
+                severity: Severity::Critical,
+                source: "s3mon".to_string(),
+                retries: 3,
+                retry_delay: 1,
+                timeout: 10,
+            })
+        );
+        let stub = events.get(1);
+        assert_eq!(
+            stub.map(|e| e.url.as_str()),
+            Some("http://127.0.0.1:8080/v2/enqueue")
+        );
+        assert_eq!(stub.map(|e| e.severity), Some(Severity::Warning));
+        assert_eq!(stub.map(|e| e.source.as_str()), Some("backup-host"));
+        assert_eq!(stub.map(|e| e.retries), Some(0));
+        assert!(serde_yaml::from_str::<EventsApi>("url: http://stub\n").is_err());
+        let invalid = "routing_key: R0UT1NGK3Y\ntimeout: 0s\n";
+        assert!(serde_yaml::from_str::<EventsApi>(invalid).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,194 @@
+use crate::config::{Content, ContentFormat};
+use anyhow::Result;
+use flate2::read::MultiGzDecoder;
+use std::io::{BufReader, ErrorKind, Read};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Validate the downloaded bytes of an object against the configured content check.
+///
+/// `truncated` must be set when `data` only holds the first `max_bytes` of a
+/// larger object; an unexpected end of input is then accepted, so only the
+/// bytes that were read are validated.
+///
+/// # Errors
+///
+/// Returns an error describing why the content is invalid.
+pub fn validate(content: &Content, data: &[u8], truncated: bool) -> Result<()> {
+    match content.format {
+        ContentFormat::Gzip => drain(MultiGzDecoder::new(data), truncated),
+        ContentFormat::Zstd => drain(zstd::Decoder::new(data)?, truncated),
+        ContentFormat::Json => {
+            let reader = BufReader::new(decompress(data)?);
+            match serde_json::from_reader::<_, serde::de::IgnoredAny>(reader) {
+                Err(e) if !(truncated && json_eof(&e)) => Err(anyhow::anyhow!("invalid JSON: {e}")),
+                _ => Ok(()),
+            }
+        }
+        ContentFormat::Ndjson => {
+            let reader = BufReader::new(decompress(data)?);
+            for value in
+                serde_json::Deserializer::from_reader(reader).into_iter::<serde::de::IgnoredAny>()
+            {
+                match value {
+                    Ok(_) => {}
+                    Err(e) if truncated && json_eof(&e) => break,
+                    Err(e) => anyhow::bail!("invalid NDJSON: {e}"),
+                }
+            }
+            Ok(())
+        }
+        ContentFormat::Tar => {
+            let mut archive = tar::Archive::new(decompress(data)?);
+            let mut entries = 0_usize;
+            for entry in archive.entries()? {
+                match entry.and_then(|mut e| std::io::copy(&mut e, &mut std::io::sink())) {
+                    Ok(_) => entries += 1,
+                    Err(e) if truncated && e.kind() == ErrorKind::UnexpectedEof => break,
+                    Err(e) => anyhow::bail!("invalid tar archive: {e}"),
+                }
+            }
+            if entries == 0 {
+                anyhow::bail!("tar archive has no entries");
+            }
+            Ok(())
+        }
+        ContentFormat::Marker => {
+            if content.marker.is_empty() {
+                anyhow::bail!("marker format requires a non-empty marker");
+            }
+            if data
+                .trim_ascii_end()
+                .ends_with(content.marker.trim_ascii_end().as_bytes())
+            {
+                Ok(())
+            } else {
+                anyhow::bail!("object does not end with the configured marker")
+            }
+        }
+    }
+}
+
+/// Number of trailing bytes to fetch for a marker check, or `None` when the
+/// object is downloaded from the start.
+#[must_use]
+pub fn tail_len(content: &Content) -> Option<u64> {
+    match content.format {
+        // Leave room for trailing whitespace such as a final newline.
+        ContentFormat::Marker => Some(content.marker.len() as u64 + 64),
+        _ => None,
+    }
+}
+
+/// Wrap `data` in a decoder when it starts with a gzip or zstd header, so
+/// compressed JSON, NDJSON and tar objects can be validated directly.
+fn decompress(data: &[u8]) -> Result<Box<dyn Read + '_>> {
+    if data.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(MultiGzDecoder::new(data)))
+    } else if data.starts_with(ZSTD_MAGIC) {
+        Ok(Box::new(zstd::Decoder::new(data)?))
+    } else {
+        Ok(Box::new(data))
+    }
+}
+
+fn drain(mut reader: impl Read, truncated: bool) -> Result<()> {
+    match std::io::copy(&mut reader, &mut std::io::sink()) {
+        Err(e) if !(truncated && e.kind() == ErrorKind::UnexpectedEof) => {
+            Err(anyhow::anyhow!("corrupt stream: {e}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn json_eof(e: &serde_json::Error) -> bool {
+    e.is_eof() || e.io_error_kind() == Some(ErrorKind::UnexpectedEof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn content(format: ContentFormat) -> Content {
+        Content {
+            format,
+            marker: String::new(),
+            max_bytes: 1024,
+        }
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        let _ = encoder.write_all(data);
+        encoder.finish().unwrap_or_default()
+    }
+
+    fn tarball() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        let _ = builder.append_data(&mut header, "dump.sql", &b"hello"[..]);
+        builder.into_inner().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_gzip() {
+        let data = gzip(&[b'x'; 4096]);
+        assert!(validate(&content(ContentFormat::Gzip), &data, false).is_ok());
+        assert!(validate(&content(ContentFormat::Gzip), b"not gzip", false).is_err());
+
+        let half = data.get(..data.len() / 2).unwrap_or_default();
+        assert!(validate(&content(ContentFormat::Gzip), half, false).is_err());
+        assert!(validate(&content(ContentFormat::Gzip), half, true).is_ok());
+    }
+
+    #[test]
+    fn test_zstd() {
+        let data = zstd::encode_all(&[b'x'; 4096][..], 0).unwrap_or_default();
+        assert!(validate(&content(ContentFormat::Zstd), &data, false).is_ok());
+        assert!(validate(&content(ContentFormat::Zstd), b"not zstd", false).is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let c = content(ContentFormat::Json);
+        assert!(validate(&c, br#"{"ok": [1, 2, 3]}"#, false).is_ok());
+        assert!(validate(&c, &gzip(br#"{"ok": true}"#), false).is_ok());
+        assert!(validate(&c, br#"{"ok": [1, 2"#, false).is_err());
+        assert!(validate(&c, br#"{"ok": [1, 2"#, true).is_ok());
+        assert!(validate(&c, br#"{"ok": ]"#, true).is_err());
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let c = content(ContentFormat::Ndjson);
+        assert!(validate(&c, b"{\"a\":1}\n{\"b\":2}\n", false).is_ok());
+        assert!(validate(&c, b"{\"a\":1}\nnot json\n", false).is_err());
+        assert!(validate(&c, b"{\"a\":1}\n{\"b\":", true).is_ok());
+    }
+
+    #[test]
+    fn test_tar() {
+        let c = content(ContentFormat::Tar);
+        let data = tarball();
+        assert!(validate(&c, &data, false).is_ok());
+        assert!(validate(&c, &gzip(&data), false).is_ok());
+        assert!(validate(&c, &[0_u8; 1024], false).is_err());
+    }
+
+    #[test]
+    fn test_marker() {
+        let c = Content {
+            marker: "-- Dump completed".to_string(),
+            ..content(ContentFormat::Marker)
+        };
+        assert!(validate(&c, b"...\n-- Dump completed\n", false).is_ok());
+        assert!(validate(&c, b"...\n-- Dump in progress\n", false).is_err());
+        assert!(validate(&content(ContentFormat::Marker), b"anything", false).is_err());
+        assert_eq!(tail_len(&c), Some(81));
+        assert_eq!(tail_len(&content(ContentFormat::Gzip)), None);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::output::{human_age, human_size};
+use crate::s3::ObjectInfo;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Uploads less than this many seconds apart belong to the same run.
+const RUN_GAP: i64 = 60;
+/// Number of most recent objects whose median size suggests `size`.
+const RECENT: usize = 10;
+
+/// Objects sharing a key prefix and extension, proposed as one check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub prefix: String,
+    pub suffix: String,
+    pub objects: usize,
+    /// Median time in seconds between runs of uploads; `None` with a single run.
+    pub cadence: Option<i64>,
+    /// Last modification of the newest object (Unix seconds).
+    pub newest: i64,
+    /// Median size of the most recent objects.
+    pub typical_size: i64,
+}
+
+impl Group {
+    /// Suggested `age`: one and a half times the cadence, rounded up to the
+    /// hour (or minute for sub-hourly uploads).
+    #[must_use]
+    pub fn age(&self) -> Option<i64> {
+        self.cadence.map(|cadence| {
+            let age = cadence.saturating_mul(3) / 2;
+            let unit = if age >= 3_600 { 3_600 } else { 60 };
+            ((age + unit - 1) / unit).max(1) * unit
+        })
+    }
+
+    /// Suggested `size`: half the typical size rounded down to a whole
+    /// number of its unit, or `0` for tiny objects.
+    #[must_use]
+    pub fn size(&self) -> i64 {
+        let half = self.typical_size / 2;
+        let unit = [1 << 30, 1 << 20, 1 << 10]
+            .into_iter()
+            .find(|unit| half >= *unit)
+            .unwrap_or(1);
+        half / unit * unit
+    }
+}
+
+/// The check prefix and suffix a key belongs to.
+///
+/// Trailing directories that are mostly digits, such as `2025/10/17/`, are
+/// dropped, and the file name is cut before its first digit, so dated keys
+/// like `db/2025/10/17/dump-1760752800.sql.gz` map to `db/dump-` and
+/// `.sql.gz`.  The suffix is the extension of up to two short parts.
+#[must_use]
+pub fn group_key(key: &str) -> (String, String) {
+    let (dir, name) = key.rsplit_once('/').unwrap_or(("", key));
+
+    let mut segments: Vec<&str> = dir.split('/').filter(|s| !s.is_empty()).collect();
+    while segments.last().is_some_and(|segment| {
+        let digits = segment.chars().filter(char::is_ascii_digit).count();
+        digits > segment.chars().filter(char::is_ascii_alphabetic).count()
+    }) {
+        segments.pop();
+    }
+
+    let parts: Vec<&str> = name.split('.').collect();
+    let extensions = parts
+        .iter()
+        .skip(1)
+        .rev()
+        .take(2)
+        .take_while(|part| {
+            !part.is_empty() && part.len() <= 4 && part.chars().any(|c| c.is_ascii_alphabetic())
+        })
+        .count();
+    let stem_len = parts.len() - extensions;
+    let stem = parts.get(..stem_len).unwrap_or_default().join(".");
+    let suffix = if extensions == 0 {
+        String::new()
+    } else {
+        format!(".{}", parts.get(stem_len..).unwrap_or_default().join("."))
+    };
+
+    let stem = stem
+        .find(|c: char| c.is_ascii_digit())
+        .map_or(stem.as_str(), |digit| stem.get(..digit).unwrap_or_default());
+
+    let mut prefix = segments.join("/");
+    if !prefix.is_empty() {
+        prefix.push('/');
+    }
+    prefix.push_str(stem);
+    (prefix, suffix)
+}
+
+/// Group `objects` by [`group_key`] and infer the cadence and size of each
+/// group, ordered by prefix and suffix.
+#[must_use]
+pub fn group(objects: &[ObjectInfo]) -> Vec<Group> {
+    let mut groups: BTreeMap<(String, String), Vec<&ObjectInfo>> = BTreeMap::new();
+    for object in objects.iter().filter(|o| !o.key.ends_with('/')) {
+        groups
+            .entry(group_key(&object.key))
+            .or_default()
+            .push(object);
+    }
+
+    groups
+        .into_iter()
+        .map(|((prefix, suffix), mut objects)| {
+            objects.sort_by_key(|o| o.last_modified);
+
+            let mut runs: Vec<i64> = vec![];
+            let mut previous: Option<i64> = None;
+            for o in &objects {
+                if previous.is_none_or(|previous| o.last_modified - previous > RUN_GAP) {
+                    runs.push(o.last_modified);
+                }
+                previous = Some(o.last_modified);
+            }
+            let intervals: Vec<i64> = runs
+                .windows(2)
+                .filter_map(|w| Some(w.get(1)? - w.first()?))
+                .collect();
+
+            let recent: Vec<i64> = objects.iter().rev().take(RECENT).map(|o| o.size).collect();
+
+            Group {
+                prefix,
+                suffix,
+                objects: objects.len(),
+                cadence: median(intervals),
+                newest: objects.last().map(|o| o.last_modified).unwrap_or_default(),
+                typical_size: median(recent).unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+fn median(mut values: Vec<i64>) -> Option<i64> {
+    values.sort_unstable();
+    values.get(values.len() / 2).copied()
+}
+
+/// Connection settings written to the proposed config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Connection {
+    pub endpoint: String,
+    pub region: String,
+}
+
+/// A proposed config YAML for `bucket` with one check per group of at
+/// least two objects; the remaining groups are listed in comments.
+#[must_use]
+pub fn config_yaml(
+    bucket: &str,
+    connection: &Connection,
+    objects: usize,
+    groups: &[Group],
+    now: i64,
+) -> String {
+    let mut out = format!(
+        "---\n# Starter config proposed by `s3mon init` from {objects} objects in {bucket}.\n\
+         # Review the prefixes and thresholds before use.\ns3mon:\n"
+    );
+    if !connection.endpoint.is_empty() {
+        let _ = writeln!(out, "  endpoint: {}", scalar(&connection.endpoint));
+    }
+    if !connection.region.is_empty() {
+        let _ = writeln!(out, "  region: {}", scalar(&connection.region));
+    }
+
+    let (checks, skipped): (Vec<&Group>, Vec<&Group>) = groups.iter().partition(|g| g.objects > 1);
+    let _ = write!(out, "  buckets:\n    {}:", scalar(bucket));
+    if checks.is_empty() {
+        out.push_str(" []\n");
+    } else {
+        out.push('\n');
+    }
+
+    for g in checks {
+        let cadence = g.cadence.map_or_else(
+            || "in a single run".to_string(),
+            |cadence| format!("every {}", human_age(cadence)),
+        );
+        let _ = writeln!(
+            out,
+            "      # {} objects uploaded {cadence}, newest {} old, typically {}",
+            g.objects,
+            human_age(now - g.newest),
+            human_size(g.typical_size)
+        );
+        let _ = writeln!(out, "      - prefix: {}", scalar(&g.prefix));
+        if !g.suffix.is_empty() {
+            let _ = writeln!(out, "        suffix: {}", scalar(&g.suffix));
+        }
+        if let Some(age) = g.age() {
+            let _ = writeln!(out, "        age: {age}  # {}", human_age(age));
+            if now - g.newest > age {
+                out.push_str(
+                    "        # The newest object is older than `age`: this check fails now.\n",
+                );
+            }
+        }
+        if g.size() > 0 {
+            let _ = writeln!(
+                out,
+                "        size: {}  # {}",
+                g.size(),
+                human_size(g.size())
+            );
+        }
+    }
+
+    if !skipped.is_empty() {
+        out.push_str("# Not proposed, only a single object:\n");
+        for g in skipped {
+            let _ = writeln!(out, "#   {}*{}", g.prefix, g.suffix);
+        }
+    }
+    out
+}
+
+/// `text` as a YAML scalar, quoted when needed.
+fn scalar(text: &str) -> String {
+    serde_yaml::to_string(text).map_or_else(|_| format!("{text:?}"), |s| s.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    const NOW: i64 = 1_760_752_800;
+    const DAY: i64 = 86_400;
+
+    fn object(key: &str, size: i64, last_modified: i64) -> ObjectInfo {
+        ObjectInfo {
+            key: key.to_string(),
+            size,
+            last_modified,
+            storage_class: None,
+        }
+    }
+
+    #[test]
+    fn test_group_key() {
+        let key = |k: &str| {
+            let (prefix, suffix) = group_key(k);
+            format!("{prefix}|{suffix}")
+        };
+        assert_eq!(
+            key("db/2025/10/17/dump-1760752800.sql.gz"),
+            "db/dump-|.sql.gz"
+        );
+        assert_eq!(key("postgresql-2026-02-09_1826.log"), "postgresql-|.log");
+        assert_eq!(key("logs/app-2025-10-17/app.log.gz"), "logs/app|.log.gz");
+        assert_eq!(key("db1/latest.tar.zst"), "db1/latest|.tar.zst");
+        assert_eq!(key("backup.2025.10.17.tar.gz"), "backup.|.tar.gz");
+        assert_eq!(key("20251017.bz2"), "|.bz2");
+        assert_eq!(key("etc/README"), "etc/README|");
+    }
+
+    #[test]
+    fn test_group() {
+        let mut objects: Vec<ObjectInfo> = (0..5)
+            .map(|day| object(&format!("db/dump-{day}.sql.gz"), 3 << 20, NOW - day * DAY))
+            .collect();
+        // A second part uploaded in the same run.
+        objects.push(object("db/dump-0b.sql.gz", 1 << 20, NOW + 30));
+        objects.push(object("README.md", 100, NOW));
+
+        let groups = group(&objects);
+        assert_eq!(groups.len(), 2);
+        let db = groups.get(1);
+        assert_eq!(db.map(|g| g.prefix.as_str()), Some("db/dump-"));
+        assert_eq!(db.map(|g| g.objects), Some(6));
+        assert_eq!(db.and_then(|g| g.cadence), Some(DAY));
+        assert_eq!(db.and_then(Group::age), Some(DAY * 3 / 2));
+        assert_eq!(db.map(|g| g.typical_size), Some(3 << 20));
+        assert_eq!(db.map(Group::size), Some(1 << 20));
+
+        let readme = groups.first();
+        assert_eq!(readme.and_then(|g| g.cadence), None);
+        assert_eq!(readme.map(Group::size), Some(50));
+    }
+
+    #[test]
+    fn test_config_yaml() -> Result<(), serde_yaml::Error> {
+        let objects = vec![
+            object("db/dump-1.sql.gz", 5 << 20, NOW - 3 * DAY),
+            object("db/dump-2.sql.gz", 5 << 20, NOW - 2 * DAY),
+            object("hourly/0001.json", 4_000, NOW - 5_400),
+            object("hourly/0002.json", 4_000, NOW - 1_800),
+            object("notes: todo.txt", 1, NOW),
+        ];
+        let connection = Connection {
+            endpoint: "http://localhost:9000".to_string(),
+            region: "us-east-1".to_string(),
+        };
+        let yaml = config_yaml("backups", &connection, objects.len(), &group(&objects), NOW);
+
+        assert!(yaml.contains("# 2 objects uploaded every 1d, newest 2d old, typically 5.0 MiB"));
+        assert!(yaml.contains("The newest object is older than `age`"));
+        assert!(yaml.contains("#   notes: todo*.txt"));
+
+        let config: Config = serde_yaml::from_str(&yaml)?;
+        assert_eq!(config.s3mon.endpoint, "http://localhost:9000");
+        let checks = config
+            .s3mon
+            .buckets
+            .get("backups")
+            .cloned()
+            .unwrap_or_default();
+        let summary: Vec<(&str, &str, i64, i64)> = checks
+            .iter()
+            .map(|o| (o.prefix.as_str(), o.suffix.as_str(), o.age, o.size))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("db/dump-", ".sql.gz", 36 * 3_600, 2 << 20),
+                ("hourly/", ".json", 7_200, 1_024),
+            ]
+        );
+
+        let yaml = config_yaml("empty", &Connection::default(), 0, &[], NOW);
+        let config: Config = serde_yaml::from_str(&yaml)?;
+        assert_eq!(config.s3mon.buckets.get("empty").map(Vec::len), Some(0));
+        Ok(())
+    }
+}
@@ -1,4 +1,11 @@
+pub mod checksum;
 pub mod cli;
 pub mod config;
+pub mod content;
+pub mod discover;
+pub mod notify;
 pub mod output;
 pub mod s3;
+pub mod schedule;
+pub mod send;
+pub mod state;
@@ -1,8 +1,34 @@
 use anyhow::Result;
+use s3mon::cli::actions::run::{ChecksFailed, ChecksUnknown};
+use std::process::ExitCode;
+
+/// Exit code for errors that prevent the checks from running.
+const EXIT_FAILURE: u8 = 1;
+
+/// Exit code for such errors with the Nagios format (plugin `UNKNOWN`).
+const EXIT_UNKNOWN: u8 = 3;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(e.downcast_ref::<ChecksFailed>().map_or_else(
+                || {
+                    if e.is::<ChecksUnknown>() {
+                        EXIT_UNKNOWN
+                    } else {
+                        EXIT_FAILURE
+                    }
+                },
+                ChecksFailed::exit_code,
+            ))
+        }
+    }
+}
+
+async fn run() -> Result<()> {
     let action = s3mon::cli::start()?;
-    s3mon::cli::actions::run::execute(&action).await?;
-    Ok(())
+    s3mon::cli::actions::run::execute(&action).await
 }
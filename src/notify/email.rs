@@ -0,0 +1,347 @@
+use super::Transition;
+use crate::config::{Email, EmailTls};
+use crate::output::{CheckResult, human_age, human_size};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::fmt::Write as _;
+use std::time::Duration;
+
+/// Why a summary mail is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// Checks are failing (without history) or started failing.
+    Failing,
+    /// Checks recovered and none started failing.
+    Recovered,
+}
+
+impl Reason {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Failing => "FAILING",
+            Self::Recovered => "RECOVERED",
+        }
+    }
+}
+
+/// Build the summary mail listing the failing `results` and the checks that
+/// `recovered`, as plain text and HTML alternatives.
+///
+/// # Errors
+///
+/// Returns an error if an address is invalid or the message cannot be built.
+pub fn message(
+    email: &Email,
+    reason: Reason,
+    results: &[CheckResult],
+    recovered: &[&Transition],
+    now: DateTime<Utc>,
+) -> Result<Message> {
+    let failing: Vec<&CheckResult> = results.iter().filter(|r| r.counts_as_failure()).collect();
+
+    let subject = email
+        .subject
+        .replace("{status}", reason.as_str())
+        .replace("{failed}", &failing.len().to_string())
+        .replace("{total}", &results.len().to_string());
+
+    let mut builder = Message::builder()
+        .from(email.from.parse::<Mailbox>()?)
+        .subject(subject);
+    for to in &email.to {
+        builder = builder.to(to.parse::<Mailbox>()?);
+    }
+
+    let text = text_body(&failing, results.len(), recovered, now);
+    let html = html_body(&failing, results.len(), recovered, now);
+    Ok(builder.multipart(MultiPart::alternative_plain_html(text, html))?)
+}
+
+/// Details of a failing check: failures, severity, newest object and error.
+fn details(r: &CheckResult, now: DateTime<Utc>) -> (String, String) {
+    let newest = r.newest.as_ref().map_or_else(
+        || "none".to_string(),
+        |newest| {
+            format!(
+                "{} old, {}",
+                human_age(now.timestamp() - newest.last_modified),
+                human_size(newest.size)
+            )
+        },
+    );
+    let failures = r.failures();
+    let failures = if failures.is_empty() {
+        "recovering".to_string()
+    } else {
+        failures.join(", ")
+    };
+    (failures, newest)
+}
+
+fn text_body(
+    failing: &[&CheckResult],
+    total: usize,
+    recovered: &[&Transition],
+    now: DateTime<Utc>,
+) -> String {
+    let mut out = format!("{} of {total} checks failing.\n", failing.len());
+    if !failing.is_empty() {
+        out.push('\n');
+    }
+    for r in failing {
+        let (failures, newest) = details(r, now);
+        let _ = write!(
+            out,
+            "- {}/{}: {failures} ({}, newest object: {newest})",
+            r.bucket,
+            r.path(),
+            r.severity.as_str()
+        );
+        if let Some(error) = &r.error_message {
+            let _ = write!(out, "\n  {error}");
+        }
+        out.push('\n');
+    }
+    if !recovered.is_empty() {
+        out.push_str("\nRecovered:\n\n");
+        for t in recovered {
+            let _ = writeln!(out, "- {}/{}", t.bucket, t.path);
+        }
+    }
+    out
+}
+
+fn html_body(
+    failing: &[&CheckResult],
+    total: usize,
+    recovered: &[&Transition],
+    now: DateTime<Utc>,
+) -> String {
+    let mut out = String::from("<html><body style=\"font-family: sans-serif\">\n");
+    let _ = writeln!(
+        out,
+        "<p><strong>{} of {total} checks failing.</strong></p>",
+        failing.len()
+    );
+    if !failing.is_empty() {
+        out.push_str(
+            "<table border=\"1\" cellpadding=\"4\" cellspacing=\"0\">\n<tr><th>Bucket</th><th>Path</th><th>Severity</th><th>Failures</th><th>Newest object</th><th>Error</th></tr>\n",
+        );
+        for r in failing {
+            let (failures, newest) = details(r, now);
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&r.bucket),
+                escape_html(&r.path()),
+                r.severity.as_str(),
+                escape_html(&failures),
+                escape_html(&newest),
+                escape_html(r.error_message.as_deref().unwrap_or_default()),
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    if !recovered.is_empty() {
+        out.push_str("<p><strong>Recovered:</strong></p>\n<ul>\n");
+        for t in recovered {
+            let _ = writeln!(
+                out,
+                "<li>{}/{}</li>",
+                escape_html(&t.bucket),
+                escape_html(&t.path)
+            );
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// SMTP transport to the server of `email`, reused for every mail.
+///
+/// # Errors
+///
+/// Returns an error if the TLS parameters cannot be built for `host`.
+pub fn transport(email: &Email) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let builder = match email.tls {
+        EmailTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&email.host)?,
+        EmailTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&email.host)?,
+        EmailTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&email.host),
+    };
+    let mut builder = builder.port(email.port()).timeout(Some(Duration::from_secs(
+        u64::try_from(email.timeout).unwrap_or_default(),
+    )));
+    if !email.username.is_empty() {
+        builder = builder.credentials(Credentials::new(
+            email.username.clone(),
+            email.password.clone(),
+        ));
+    }
+    Ok(builder.build())
+}
+
+/// Deliver `message` through `transport`.
+///
+/// # Errors
+///
+/// Returns an error if the connection, TLS handshake, authentication or
+/// delivery fails.
+pub async fn send(transport: &AsyncSmtpTransport<Tokio1Executor>, message: Message) -> Result<()> {
+    transport.send(message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+    use crate::s3::ObjectInfo;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    fn email(port: u16) -> Email {
+        Email {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            tls: EmailTls::None,
+            username: String::new(),
+            password: String::new(),
+            from: "s3mon@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+            subject: "[s3mon] {status}: {failed} of {total} checks failing".to_string(),
+            recovery: true,
+            timeout: 5,
+        }
+    }
+
+    fn results(now: DateTime<Utc>) -> Vec<CheckResult> {
+        vec![
+            CheckResult {
+                bucket: "backups".to_string(),
+                prefix: "db/".to_string(),
+                error: true,
+                error_message: Some("access <denied>".to_string()),
+                newest: Some(ObjectInfo {
+                    key: "db/dump.sql.gz".to_string(),
+                    size: 2048,
+                    last_modified: now.timestamp() - 93_600,
+                    storage_class: None,
+                }),
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "backups".to_string(),
+                prefix: "logs/".to_string(),
+                exist: true,
+                ..CheckResult::default()
+            },
+        ]
+    }
+
+    /// Accept one SMTP session and return the message data.
+    async fn sink(listener: TcpListener) -> Result<String> {
+        let (stream, _) = listener.accept().await?;
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        write.write_all(b"220 sink ESMTP\r\n").await?;
+
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await? {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    write.write_all(b"250 queued\r\n").await?;
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let command = line.to_uppercase();
+            if command.starts_with("EHLO") || command.starts_with("HELO") {
+                write.write_all(b"250 sink\r\n").await?;
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                write.write_all(b"354 go ahead\r\n").await?;
+            } else if command.starts_with("QUIT") {
+                write.write_all(b"221 bye\r\n").await?;
+                break;
+            } else {
+                write.write_all(b"250 ok\r\n").await?;
+            }
+        }
+        Ok(data)
+    }
+
+    #[test]
+    fn test_bodies() {
+        let now = Utc::now();
+        let results = results(now);
+        let failing: Vec<&CheckResult> = results.iter().filter(|r| r.counts_as_failure()).collect();
+        let recovered = Transition {
+            bucket: "backups".to_string(),
+            prefix: "logs/".to_string(),
+            suffix: String::new(),
+            subdir: String::new(),
+            path: "logs/".to_string(),
+            failing: false,
+            severity: Severity::Critical,
+            failures: Vec::new(),
+            age: None,
+            error: None,
+        };
+
+        let text = text_body(&failing, results.len(), &[&recovered], now);
+        assert_eq!(
+            text,
+            "1 of 2 checks failing.\n\n\
+             - backups/db/: error (critical, newest object: 1d 2h old, 2.0 KiB)\n  access <denied>\n\n\
+             Recovered:\n\n- backups/logs/\n"
+        );
+
+        let html = html_body(&failing, results.len(), &[&recovered], now);
+        assert!(html.contains("<td>backups</td><td>db/</td><td>critical</td><td>error</td>"));
+        assert!(html.contains("<td>access &lt;denied&gt;</td>"));
+        assert!(html.contains("<li>backups/logs/</li>"));
+    }
+
+    #[tokio::test]
+    async fn test_send_to_sink() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let server = tokio::spawn(sink(listener));
+
+        let now = Utc::now();
+        let email = email(port);
+        let message = message(&email, Reason::Failing, &results(now), &[], now)?;
+        send(&transport(&email)?, message).await?;
+
+        let data = server.await??;
+        assert!(data.contains("Subject: [s3mon] FAILING: 1 of 2 checks failing"));
+        assert!(data.contains("To: ops@example.com"));
+        assert!(data.contains("multipart/alternative"));
+        assert!(data.contains("text/plain"));
+        assert!(data.contains("text/html"));
+        assert!(data.contains("backups/db/: error"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        let mut email = email(25);
+        email.to = vec!["not an address".to_string()];
+        assert!(message(&email, Reason::Recovered, &[], &[], Utc::now()).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use super::Transition;
+use super::webhook::post;
+use crate::config::EventsApi;
+use anyhow::Result;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+/// Longest `dedup_key` accepted by the Events API.
+const MAX_DEDUP_KEY: usize = 255;
+
+/// Key identifying the incident of a check, e.g. `s3mon:backups:db/:.sql.gz`.
+///
+/// It only depends on the bucket, prefix, suffix and subdirectory of the
+/// check, so the event sent on recovery resolves the incident opened when it
+/// started failing.  Keys that would exceed the limit of the API are
+/// replaced by their SHA-256 digest.
+#[must_use]
+pub fn dedup_key(transition: &Transition) -> String {
+    let mut key = format!(
+        "s3mon:{}:{}:{}",
+        transition.bucket, transition.prefix, transition.suffix
+    );
+    if !transition.subdir.is_empty() {
+        key.push(':');
+        key.push_str(&transition.subdir);
+    }
+    if key.len() > MAX_DEDUP_KEY {
+        key = format!("s3mon:sha256:{:x}", Sha256::digest(key.as_bytes()));
+    }
+    key
+}
+
+/// Event triggering the incident of a failing check or resolving it once
+/// the check recovered.
+#[must_use]
+pub fn event(api: &EventsApi, transition: &Transition) -> Value {
+    if !transition.failing {
+        return json!({
+            "routing_key": api.routing_key,
+            "event_action": "resolve",
+            "dedup_key": dedup_key(transition),
+        });
+    }
+
+    json!({
+        "routing_key": api.routing_key,
+        "event_action": "trigger",
+        "dedup_key": dedup_key(transition),
+        "payload": {
+            "summary": transition.summary(),
+            "source": api.source,
+            "severity": transition.severity.as_str(),
+            "component": transition.bucket,
+            "group": transition.prefix,
+            "class": transition.failures.join(", "),
+            "custom_details": {
+                "path": transition.path,
+                "suffix": transition.suffix,
+                "subdir": transition.subdir,
+                "failures": transition.failures,
+                "newest_age_seconds": transition.age,
+                "error": transition.error,
+            },
+        },
+    })
+}
+
+/// Send the [`event`] for `transition` to `api`, retrying like webhooks.
+///
+/// # Errors
+///
+/// Returns the error of the last attempt when every attempt failed.
+pub async fn send(
+    client: &reqwest::Client,
+    api: &EventsApi,
+    transition: &Transition,
+) -> Result<()> {
+    post(
+        client,
+        &api.url,
+        &event(api, transition),
+        api.retries,
+        api.retry_delay,
+        api.timeout,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+    use crate::notify::tests::{field, serve};
+    use tokio::net::TcpListener;
+
+    fn api(url: String) -> EventsApi {
+        EventsApi {
+            routing_key: "R0UT1NGK3Y".to_string(),
+            url,
+            severity: Severity::Critical,
+            source: "s3mon".to_string(),
+            retries: 0,
+            retry_delay: 0,
+            timeout: 5,
+        }
+    }
+
+    fn transition(failing: bool) -> Transition {
+        Transition {
+            bucket: "backups".to_string(),
+            prefix: "db/".to_string(),
+            suffix: ".sql.gz".to_string(),
+            subdir: String::new(),
+            path: "db/.sql.gz".to_string(),
+            failing,
+            severity: Severity::Critical,
+            failures: if failing {
+                vec!["age".to_string(), "size".to_string()]
+            } else {
+                Vec::new()
+            },
+            age: Some(90_000),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_dedup_key() {
+        let mut t = transition(true);
+        assert_eq!(dedup_key(&t), "s3mon:backups:db/:.sql.gz");
+        assert_eq!(dedup_key(&t), dedup_key(&transition(false)));
+
+        t.subdir = "2025/".to_string();
+        assert_eq!(dedup_key(&t), "s3mon:backups:db/:.sql.gz:2025/");
+
+        t.prefix = "x".repeat(300);
+        let key = dedup_key(&t);
+        assert!(key.starts_with("s3mon:sha256:"));
+        assert_eq!(key.len(), "s3mon:sha256:".len() + 64);
+    }
+
+    #[test]
+    fn test_event() {
+        let api = api("http://stub".to_string());
+
+        let trigger = event(&api, &transition(true));
+        assert_eq!(field(&trigger, "/routing_key"), "R0UT1NGK3Y");
+        assert_eq!(field(&trigger, "/event_action"), "trigger");
+        assert_eq!(field(&trigger, "/dedup_key"), "s3mon:backups:db/:.sql.gz");
+        assert_eq!(
+            field(&trigger, "/payload/summary"),
+            "S3MON FAIL backups/db/.sql.gz: age, size"
+        );
+        assert_eq!(field(&trigger, "/payload/severity"), "critical");
+        assert_eq!(field(&trigger, "/payload/source"), "s3mon");
+        assert_eq!(field(&trigger, "/payload/component"), "backups");
+        assert_eq!(
+            field(&trigger, "/payload/custom_details/newest_age_seconds"),
+            90_000
+        );
+
+        let resolve = event(&api, &transition(false));
+        assert_eq!(field(&resolve, "/event_action"), "resolve");
+        assert_eq!(field(&resolve, "/dedup_key"), "s3mon:backups:db/:.sql.gz");
+        assert_eq!(field(&resolve, "/payload"), &Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_send_to_stub() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let api = api(format!("http://{}/v2/enqueue", listener.local_addr()?));
+        let server = tokio::spawn(serve(listener, vec![202, 202]));
+
+        let client = reqwest::Client::new();
+        send(&client, &api, &transition(true)).await?;
+        send(&client, &api, &transition(false)).await?;
+
+        let bodies = server
+            .await?
+            .iter()
+            .map(|body| serde_json::from_str(body))
+            .collect::<Result<Vec<Value>, _>>()?;
+        let actions: Vec<&Value> = bodies
+            .iter()
+            .map(|body| field(body, "/event_action"))
+            .collect();
+        assert_eq!(actions, ["trigger", "resolve"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,408 @@
+pub mod email;
+pub mod events;
+mod webhook;
+
+pub use webhook::{body, deliver};
+
+use crate::config;
+use crate::output::CheckResult;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use std::collections::BTreeMap;
+
+/// Whether each check was failing, keyed by [`check_key`].
+pub type States = BTreeMap<String, bool>;
+
+/// A check that started (OK→FAIL) or stopped (FAIL→OK) failing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub bucket: String,
+    pub prefix: String,
+    pub suffix: String,
+    pub subdir: String,
+    /// `prefix`, `subdir` and `suffix` combined, see [`CheckResult::path`].
+    pub path: String,
+    /// `true` when the check started failing, `false` when it recovered.
+    pub failing: bool,
+    pub severity: config::Severity,
+    /// What failed, see [`CheckResult::failures`]; empty on recovery.
+    pub failures: Vec<String>,
+    /// Seconds since the newest matching object was modified.
+    pub age: Option<i64>,
+    /// Text of the S3 API error, if any.
+    pub error: Option<String>,
+}
+
+impl Transition {
+    #[must_use]
+    pub const fn status(&self) -> &'static str {
+        if self.failing { "FAIL" } else { "OK" }
+    }
+
+    /// One line description such as `S3MON FAIL backups/db/: missing`.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        if self.failing {
+            format!(
+                "S3MON FAIL {}/{}: {}",
+                self.bucket,
+                self.path,
+                self.failures.join(", ")
+            )
+        } else {
+            format!("S3MON OK {}/{} recovered", self.bucket, self.path)
+        }
+    }
+}
+
+/// Key identifying a check across runs: the bucket and the checked path.
+#[must_use]
+pub fn check_key(result: &CheckResult) -> String {
+    format!("{}/{}", result.bucket, result.path())
+}
+
+/// Whether each result is failing, see [`CheckResult::is_failing`].
+#[must_use]
+pub fn states(results: &[CheckResult]) -> States {
+    results
+        .iter()
+        .map(|r| (check_key(r), r.is_failing()))
+        .collect()
+}
+
+/// Checks whose state differs from `previous`.
+///
+/// Checks missing from `previous`, e.g. newly configured ones, establish
+/// their state without being reported.  Silenced and `info` checks are
+/// never reported.
+#[must_use]
+pub fn transitions(
+    previous: &States,
+    results: &[CheckResult],
+    now: DateTime<Utc>,
+) -> Vec<Transition> {
+    results
+        .iter()
+        .filter(|r| !r.silenced && r.severity > config::Severity::Info)
+        .filter_map(|r| {
+            let failing = r.is_failing();
+            let was_failing = *previous.get(&check_key(r))?;
+            (failing != was_failing).then(|| transition(r, failing, now))
+        })
+        .collect()
+}
+
+fn transition(r: &CheckResult, failing: bool, now: DateTime<Utc>) -> Transition {
+    Transition {
+        bucket: r.bucket.clone(),
+        prefix: r.prefix.clone(),
+        suffix: r.suffix.clone(),
+        subdir: r.subdir.clone(),
+        path: r.path(),
+        failing,
+        severity: r.severity,
+        failures: if failing { r.failures() } else { Vec::new() },
+        age: r
+            .newest
+            .as_ref()
+            .map(|newest| now.timestamp() - newest.last_modified),
+        error: r.error_message.clone(),
+    }
+}
+
+/// Sends the configured notifications for state changes between runs.
+#[derive(Debug)]
+pub struct Notifier {
+    config: config::Notify,
+    client: reqwest::Client,
+    mailer: Option<AsyncSmtpTransport<Tokio1Executor>>,
+}
+
+impl Notifier {
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client or the SMTP transport cannot be
+    /// initialised.
+    pub fn new(config: config::Notify) -> Result<Self> {
+        Ok(Self {
+            mailer: config.email.as_ref().map(email::transport).transpose()?,
+            config,
+            client: reqwest::Client::builder().build()?,
+        })
+    }
+
+    /// Notify about the changes between `previous` and `results`.
+    ///
+    /// Without a `previous` run, webhooks are not called, incidents are
+    /// triggered for the failing checks and a mail is sent whenever a check
+    /// fails. Failed deliveries are logged and do not affect the run.
+    pub async fn notify(&self, previous: Option<&States>, results: &[CheckResult]) {
+        let now = Utc::now();
+        let changes = previous
+            .map(|previous| transitions(previous, results, now))
+            .unwrap_or_default();
+
+        for transition in &changes {
+            tracing::info!("{}", transition.summary());
+            for webhook in &self.config.webhooks {
+                if let Err(e) = deliver(&self.client, webhook, transition).await {
+                    tracing::error!("Error notifying webhook '{}': {e}", webhook.url);
+                }
+            }
+        }
+
+        let history = previous.is_some();
+        self.incidents(history, &changes, results, now).await;
+        self.mail(history, &changes, results, now).await;
+    }
+
+    /// Trigger and resolve incidents through the configured events APIs.
+    ///
+    /// Without history every run triggers the failing checks and resolves
+    /// the passing ones; the API deduplicates triggers into one open
+    /// incident per check and ignores resolves without an open incident.
+    async fn incidents(
+        &self,
+        history: bool,
+        changes: &[Transition],
+        results: &[CheckResult],
+        now: DateTime<Utc>,
+    ) {
+        if self.config.events.is_empty() {
+            return;
+        }
+        let incidents = if history {
+            changes.to_vec()
+        } else {
+            results
+                .iter()
+                .filter(|r| !r.silenced && r.severity > config::Severity::Info)
+                .map(|r| transition(r, r.is_failing(), now))
+                .collect()
+        };
+
+        for api in &self.config.events {
+            for incident in incidents.iter().filter(|t| t.severity >= api.severity) {
+                if let Err(e) = events::send(&self.client, api, incident).await {
+                    tracing::error!(
+                        "Error sending event for '{}' to '{}': {e}",
+                        events::dedup_key(incident),
+                        api.url
+                    );
+                }
+            }
+        }
+    }
+
+    async fn mail(
+        &self,
+        history: bool,
+        changes: &[Transition],
+        results: &[CheckResult],
+        now: DateTime<Utc>,
+    ) {
+        let (Some(config), Some(mailer)) = (&self.config.email, &self.mailer) else {
+            return;
+        };
+        let reason = if !history {
+            results
+                .iter()
+                .any(CheckResult::counts_as_failure)
+                .then_some(email::Reason::Failing)
+        } else if changes.iter().any(|t| t.failing) {
+            Some(email::Reason::Failing)
+        } else if config.recovery && !changes.is_empty() {
+            Some(email::Reason::Recovered)
+        } else {
+            None
+        };
+        let Some(reason) = reason else {
+            return;
+        };
+
+        let recovered: Vec<&Transition> = changes.iter().filter(|t| !t.failing).collect();
+        let sent = match email::message(config, reason, results, &recovered, now) {
+            Ok(message) => email::send(mailer, message).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            tracing::error!("Error sending mail via '{}': {e}", config.host);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::ObjectInfo;
+    use serde_json::Value;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn result(prefix: &str, exist: bool) -> CheckResult {
+        CheckResult {
+            bucket: "backups".to_string(),
+            prefix: prefix.to_string(),
+            exist,
+            ..CheckResult::default()
+        }
+    }
+
+    /// Answer one request per connection with the next of `statuses` and
+    /// return the request bodies.
+    pub(super) async fn serve(listener: TcpListener, statuses: Vec<u16>) -> Vec<String> {
+        let mut bodies = vec![];
+        for status in statuses {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                break;
+            };
+            let mut request = vec![];
+            let mut buf = [0u8; 4096];
+            let body = loop {
+                let Ok(read) = stream.read(&mut buf).await else {
+                    break String::new();
+                };
+                request.extend_from_slice(buf.get(..read).unwrap_or_default());
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .and_then(|value| value.trim().parse::<usize>().ok())
+                        })
+                        .unwrap_or_default();
+                    if body.len() >= length || read == 0 {
+                        break body.to_string();
+                    }
+                } else if read == 0 {
+                    break String::new();
+                }
+            };
+            bodies.push(body);
+            let _ = stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    )
+                    .as_bytes(),
+                )
+                .await;
+        }
+        bodies
+    }
+
+    pub(super) fn field<'a>(body: &'a Value, pointer: &str) -> &'a Value {
+        body.pointer(pointer).unwrap_or(&Value::Null)
+    }
+
+    #[test]
+    fn test_transitions() {
+        let now = Utc::now();
+        let previous = states(&[result("db/", true), result("logs/", false)]);
+        assert_eq!(
+            previous,
+            States::from([
+                ("backups/db/".to_string(), false),
+                ("backups/logs/".to_string(), true),
+            ])
+        );
+
+        let mut db = result("db/", false);
+        db.error = true;
+        db.error_message = Some("access denied".to_string());
+        db.newest = Some(ObjectInfo {
+            key: "db/dump.sql.gz".to_string(),
+            size: 1024,
+            last_modified: now.timestamp() - 90_000,
+            storage_class: None,
+        });
+        let current = vec![db, result("logs/", true), result("new/", false)];
+
+        let changes = transitions(&previous, &current, now);
+        assert_eq!(changes.len(), 2);
+        let failed = changes.first();
+        assert_eq!(failed.map(|t| t.failing), Some(true));
+        assert_eq!(failed.and_then(|t| t.age), Some(90_000));
+        assert_eq!(
+            failed.map(Transition::summary).as_deref(),
+            Some("S3MON FAIL backups/db/: error")
+        );
+        assert_eq!(
+            failed.and_then(|t| t.error.as_deref()),
+            Some("access denied")
+        );
+        let recovered = changes.get(1);
+        assert_eq!(recovered.map(Transition::status), Some("OK"));
+        assert_eq!(
+            recovered.map(Transition::summary).as_deref(),
+            Some("S3MON OK backups/logs/ recovered")
+        );
+    }
+
+    #[test]
+    fn test_silenced_failures_are_ok() {
+        let previous = states(&[result("db/", true)]);
+        let mut silenced = result("db/", false);
+        silenced.silenced = true;
+        assert!(transitions(&previous, &[silenced], Utc::now()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_incidents() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/v2/enqueue", listener.local_addr()?);
+        let server = tokio::spawn(serve(listener, vec![202, 202, 202, 202]));
+
+        let notifier = Notifier::new(config::Notify {
+            events: vec![config::EventsApi {
+                routing_key: "R0UT1NGK3Y".to_string(),
+                url,
+                severity: config::Severity::Critical,
+                source: "s3mon".to_string(),
+                retries: 0,
+                retry_delay: 0,
+                timeout: 5,
+            }],
+            ..config::Notify::default()
+        })?;
+
+        let warning = |exist| CheckResult {
+            severity: config::Severity::Warning,
+            ..result("logs/", exist)
+        };
+        let failing = vec![result("db/", false), warning(false)];
+        // Without history the failing critical check is triggered.
+        notifier.notify(None, &failing).await;
+        // and a passing one resolved.
+        notifier.notify(None, &[result("db/", true)]).await;
+        // Unchanged checks send nothing, recovered ones are resolved.
+        notifier.notify(Some(&states(&failing)), &failing).await;
+        let recovered = vec![result("db/", true), warning(false)];
+        notifier.notify(Some(&states(&failing)), &recovered).await;
+        notifier.notify(Some(&states(&recovered)), &failing).await;
+
+        let bodies = server
+            .await?
+            .iter()
+            .map(|body| serde_json::from_str(body))
+            .collect::<Result<Vec<Value>, _>>()?;
+        let events: Vec<(&Value, &Value)> = bodies
+            .iter()
+            .map(|body| (field(body, "/event_action"), field(body, "/dedup_key")))
+            .collect();
+        let key = Value::from("s3mon:backups:db/:");
+        assert_eq!(
+            events,
+            [
+                (&Value::from("trigger"), &key),
+                (&Value::from("resolve"), &key),
+                (&Value::from("resolve"), &key),
+                (&Value::from("trigger"), &key),
+            ]
+        );
+        Ok(())
+    }
+}
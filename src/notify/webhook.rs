@@ -0,0 +1,241 @@
+use super::Transition;
+use crate::config::{Webhook, WebhookTemplate};
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// JSON body announcing `transition` in the layout of `template`.
+#[must_use]
+pub fn body(template: WebhookTemplate, transition: &Transition) -> Value {
+    match template {
+        WebhookTemplate::Generic => json!({
+            "status": transition.status().to_lowercase(),
+            "summary": transition.summary(),
+            "bucket": transition.bucket,
+            "prefix": transition.prefix,
+            "suffix": transition.suffix,
+            "subdir": transition.subdir,
+            "path": transition.path,
+            "severity": transition.severity.as_str(),
+            "failures": transition.failures,
+            "newest_age_seconds": transition.age,
+            "error": transition.error,
+        }),
+        WebhookTemplate::Slack => json!({
+            "text": transition.summary(),
+            "attachments": [{
+                "color": if transition.failing { "danger" } else { "good" },
+                "fields": facts(transition)
+                    .into_iter()
+                    .map(|(title, value)| json!({ "title": title, "value": value, "short": true }))
+                    .collect::<Vec<_>>(),
+            }],
+        }),
+        WebhookTemplate::Teams => json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": transition.summary(),
+            "title": transition.summary(),
+            "themeColor": if transition.failing { "D70000" } else { "2DC72D" },
+            "sections": [{
+                "facts": facts(transition)
+                    .into_iter()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect::<Vec<_>>(),
+            }],
+        }),
+    }
+}
+
+/// Labelled details shown by the chat templates.
+fn facts(transition: &Transition) -> Vec<(&'static str, String)> {
+    let mut facts = vec![
+        ("Bucket", transition.bucket.clone()),
+        ("Path", transition.path.clone()),
+        ("Severity", transition.severity.as_str().to_string()),
+    ];
+    if !transition.failures.is_empty() {
+        facts.push(("Failures", transition.failures.join(", ")));
+    }
+    if let Some(age) = transition.age {
+        facts.push(("Newest object age", format!("{age}s")));
+    }
+    if let Some(error) = &transition.error {
+        facts.push(("Error", error.clone()));
+    }
+    facts
+}
+
+/// `POST` the body for `transition` to `webhook`.
+///
+/// # Errors
+///
+/// Returns the error of the last attempt when every attempt failed, see
+/// [`post`].
+pub async fn deliver(
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    transition: &Transition,
+) -> Result<()> {
+    post(
+        client,
+        &webhook.url,
+        &body(webhook.template, transition),
+        webhook.retries,
+        webhook.retry_delay,
+        webhook.timeout,
+    )
+    .await
+}
+
+/// `POST` `body` as JSON to `url`, waiting at most `timeout` seconds per
+/// attempt.
+///
+/// Connection errors, timeouts and non-2xx responses are retried up to
+/// `retries` times, doubling `retry_delay` (seconds) after each attempt.
+///
+/// # Errors
+///
+/// Returns the error of the last attempt when every attempt failed.
+pub(super) async fn post(
+    client: &reqwest::Client,
+    url: &str,
+    body: &Value,
+    retries: u32,
+    retry_delay: i64,
+    timeout: i64,
+) -> Result<()> {
+    let timeout = Duration::from_secs(u64::try_from(timeout).unwrap_or_default());
+    let mut delay = Duration::from_secs(u64::try_from(retry_delay).unwrap_or_default());
+    let mut attempt = 0;
+
+    loop {
+        let outcome = match client.post(url).timeout(timeout).json(body).send().await {
+            Ok(response) => response.error_for_status().map(drop),
+            Err(e) => Err(e),
+        };
+
+        match outcome {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                tracing::warn!(
+                    "POST to '{url}' attempt {attempt} failed, retrying in {delay:?}: {e}"
+                );
+                tokio::time::sleep(delay).await;
+                delay = delay.saturating_mul(2);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+    use crate::notify::tests::{field, serve};
+    use tokio::net::TcpListener;
+
+    fn transition(failing: bool) -> Transition {
+        Transition {
+            bucket: "backups".to_string(),
+            prefix: "db/".to_string(),
+            suffix: ".sql.gz".to_string(),
+            subdir: String::new(),
+            path: "db/.sql.gz".to_string(),
+            failing,
+            severity: Severity::Critical,
+            failures: if failing {
+                vec!["missing".to_string()]
+            } else {
+                Vec::new()
+            },
+            age: Some(90_000),
+            error: None,
+        }
+    }
+
+    fn webhook(url: String, template: WebhookTemplate, retries: u32) -> Webhook {
+        Webhook {
+            url,
+            template,
+            retries,
+            retry_delay: 0,
+            timeout: 5,
+        }
+    }
+
+    #[test]
+    fn test_generic_body() {
+        let body = body(WebhookTemplate::Generic, &transition(true));
+        assert_eq!(field(&body, "/status"), "fail");
+        assert_eq!(field(&body, "/bucket"), "backups");
+        assert_eq!(field(&body, "/prefix"), "db/");
+        assert_eq!(field(&body, "/failures"), &json!(["missing"]));
+        assert_eq!(field(&body, "/newest_age_seconds"), 90_000);
+        assert_eq!(field(&body, "/error"), &Value::Null);
+        assert_eq!(
+            field(&body, "/summary"),
+            "S3MON FAIL backups/db/.sql.gz: missing"
+        );
+    }
+
+    #[test]
+    fn test_chat_bodies() {
+        let slack = body(WebhookTemplate::Slack, &transition(false));
+        assert_eq!(
+            field(&slack, "/text"),
+            "S3MON OK backups/db/.sql.gz recovered"
+        );
+        assert_eq!(field(&slack, "/attachments/0/color"), "good");
+        assert_eq!(field(&slack, "/attachments/0/fields/0/value"), "backups");
+
+        let teams = body(WebhookTemplate::Teams, &transition(true));
+        assert_eq!(field(&teams, "/@type"), "MessageCard");
+        assert_eq!(field(&teams, "/themeColor"), "D70000");
+        assert_eq!(field(&teams, "/sections/0/facts/3/name"), "Failures");
+        assert_eq!(field(&teams, "/sections/0/facts/4/value"), "90000s");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/hook", listener.local_addr()?);
+        let server = tokio::spawn(serve(listener, vec![500, 200]));
+
+        let client = reqwest::Client::new();
+        deliver(
+            &client,
+            &webhook(url, WebhookTemplate::Generic, 1),
+            &transition(true),
+        )
+        .await?;
+
+        let bodies = server.await?;
+        assert_eq!(bodies.len(), 2);
+        for body in bodies {
+            let body: Value = serde_json::from_str(&body)?;
+            assert_eq!(field(&body, "/status"), "fail");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deliver_gives_up() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/hook", listener.local_addr()?);
+        let server = tokio::spawn(serve(listener, vec![503, 503]));
+
+        let client = reqwest::Client::new();
+        let outcome = deliver(
+            &client,
+            &webhook(url, WebhookTemplate::Slack, 1),
+            &transition(true),
+        )
+        .await;
+        assert!(outcome.is_err());
+        assert_eq!(server.await?.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use super::{CheckResult, PostureResult, sort_results, sorted_posture};
+use chrono::prelude::Utc;
+use std::fmt::Write as _;
+
+/// Default path template for the Graphite and `StatsD` formats.
+pub const DEFAULT_PATH_TEMPLATE: &str = "s3mon.{bucket}.{prefix}";
+
+/// Format results in the Graphite plaintext protocol (`path value timestamp`).
+///
+/// Metric paths are built from `template`, see [`metric_path`].
+#[must_use]
+pub fn format_graphite(
+    results: &[CheckResult],
+    posture: &[PostureResult],
+    template: &str,
+) -> String {
+    let now = Utc::now().timestamp();
+    let mut out = String::new();
+    for (path, value) in gauges(results, posture, template) {
+        let _ = writeln!(out, "{path} {value} {now}");
+    }
+    out
+}
+
+/// Format results as `StatsD` gauges (`path:value|g`).
+///
+/// Metric paths are built from `template`, see [`metric_path`].
+#[must_use]
+pub fn format_statsd(results: &[CheckResult], posture: &[PostureResult], template: &str) -> String {
+    let mut out = String::new();
+    for (path, value) in gauges(results, posture, template) {
+        let _ = writeln!(out, "{path}:{value}|g");
+    }
+    out
+}
+
+/// Expand a path template for a check.
+///
+/// `{bucket}`, `{prefix}`, `{suffix}` and `{subdir}` are replaced by the
+/// sanitized values: `/` in prefixes becomes a `.` so nested prefixes map to
+/// nested paths, and every character other than ASCII letters, digits, `-`
+/// and `_` becomes `_`.  Empty path segments are dropped.
+#[must_use]
+pub fn metric_path(
+    template: &str,
+    bucket: &str,
+    prefix: &str,
+    suffix: &str,
+    subdir: &str,
+) -> String {
+    template
+        .replace("{bucket}", &sanitize(bucket))
+        .replace("{prefix}", &sanitize(prefix))
+        .replace("{suffix}", &sanitize(suffix))
+        .replace("{subdir}", &sanitize(subdir))
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .split('/')
+        .map(|segment| {
+            segment
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+                .trim_matches('_')
+                .to_string()
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Every gauge as a (path, value) pair: the core check metrics, the optional
+/// ones that are configured, and `bucket_checks.<check>.{ok,error}` for the
+/// posture checks.
+fn gauges(
+    results: &[CheckResult],
+    posture: &[PostureResult],
+    template: &str,
+) -> Vec<(String, String)> {
+    let mut gauges = vec![];
+
+    for r in sort_results(results) {
+        let path = metric_path(template, &r.bucket, &r.prefix, &r.suffix, &r.subdir);
+        let mut values = vec![
+            ("object_exists", i32::from(r.exist).to_string()),
+            ("check_error", i32::from(r.error).to_string()),
+            ("size_mismatch", i32::from(r.size_mismatch).to_string()),
+            ("objects", r.objects.to_string()),
+        ];
+        if let Some(valid) = r.content_valid {
+            values.push(("content_valid", i32::from(valid).to_string()));
+        }
+        if let Some(mismatch) = r.checksum_mismatch {
+            values.push(("checksum_mismatch", i32::from(mismatch).to_string()));
+        }
+        if let Some(violated) = r.retention_violation {
+            values.push(("retention_violation", i32::from(violated).to_string()));
+        }
+        if let Some(count) = r.stale_objects {
+            values.push(("stale_objects", count.to_string()));
+        }
+        gauges.extend(
+            values
+                .into_iter()
+                .map(|(name, value)| (format!("{path}.{name}"), value)),
+        );
+    }
+
+    for p in sorted_posture(posture) {
+        let path = metric_path(template, &p.bucket, "bucket_checks", "", "");
+        let check = sanitize(&p.check);
+        gauges.push((format!("{path}.{check}.ok"), i32::from(p.ok).to_string()));
+        gauges.push((
+            format!("{path}.{check}.error"),
+            i32::from(p.error).to_string(),
+        ));
+    }
+
+    gauges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<CheckResult> {
+        vec![CheckResult {
+            bucket: "my.bucket".to_string(),
+            prefix: "backups/db/".to_string(),
+            exist: true,
+            objects: 4,
+            stale_objects: Some(1),
+            ..CheckResult::default()
+        }]
+    }
+
+    #[test]
+    fn test_metric_path() {
+        assert_eq!(
+            metric_path(DEFAULT_PATH_TEMPLATE, "my.bucket", "backups/db/", "", ""),
+            "s3mon.my_bucket.backups.db"
+        );
+        assert_eq!(
+            metric_path(
+                "backups.{bucket}.{prefix}.{subdir}.{suffix}",
+                "b",
+                "tenants/",
+                ".tar.gz",
+                "42"
+            ),
+            "backups.b.tenants.42.tar_gz"
+        );
+        assert_eq!(
+            metric_path("s3mon.{bucket}.{prefix}", "b", "", "", ""),
+            "s3mon.b"
+        );
+    }
+
+    #[test]
+    fn test_format_graphite() {
+        let out = format_graphite(&results(), &[], DEFAULT_PATH_TEMPLATE);
+        let now = Utc::now().timestamp();
+        let first = out.lines().next().unwrap_or_default();
+        let fields: Vec<&str> = first.split(' ').collect();
+        assert_eq!(
+            fields.first(),
+            Some(&"s3mon.my_bucket.backups.db.object_exists")
+        );
+        assert_eq!(fields.get(1), Some(&"1"));
+        assert!(
+            fields
+                .get(2)
+                .and_then(|ts| ts.parse::<i64>().ok())
+                .is_some_and(|ts| (now - ts).abs() <= 1)
+        );
+        assert!(out.contains("s3mon.my_bucket.backups.db.objects 4 "));
+        assert!(out.contains("s3mon.my_bucket.backups.db.stale_objects 1 "));
+        assert!(!out.contains("content_valid"));
+    }
+
+    #[test]
+    fn test_format_statsd() {
+        let posture = vec![PostureResult {
+            bucket: "my.bucket".to_string(),
+            check: "versioning".to_string(),
+            ok: true,
+            error: false,
+        }];
+        let out = format_statsd(&results(), &posture, "{bucket}.{prefix}");
+        assert!(out.starts_with(
+            "my_bucket.backups.db.object_exists:1|g\nmy_bucket.backups.db.check_error:0|g\n"
+        ));
+        assert!(out.ends_with(
+            "my_bucket.bucket_checks.versioning.ok:1|g\nmy_bucket.bucket_checks.versioning.error:0|g\n"
+        ));
+    }
+}
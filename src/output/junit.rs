@@ -0,0 +1,250 @@
+use super::{CheckResult, PostureResult, sort_results, sorted_posture};
+use crate::config::Severity;
+use chrono::prelude::{SecondsFormat, Utc};
+use std::fmt::Write as _;
+
+/// Format results as a `JUnit` XML report.
+///
+/// Every check becomes a `testcase` with the bucket as `classname` and the
+/// checked path (prefix and suffix) as `name`; bucket posture checks are
+/// named `bucket_checks/<check>`.  Checks pass or fail after `fail_after` and
+/// `recover_after`, like the exit status: S3 API errors are reported as
+/// `error`, other failures as `failure`, and failing silenced or `info`
+/// checks as `skipped`.
+#[must_use]
+pub fn format_junit(results: &[CheckResult], posture: &[PostureResult]) -> String {
+    let mut cases = Vec::with_capacity(results.len() + posture.len());
+
+    for r in sort_results(results) {
+        let failures = r.failures();
+        let reason = if failures.is_empty() {
+            // Passed, but not yet `recover_after` times in a row.
+            "recovering".to_string()
+        } else {
+            failures.join(", ")
+        };
+        let outcome = if !r.is_failing() {
+            Outcome::Passed
+        } else if r.silenced {
+            Outcome::Skipped(format!("silenced: {reason}"))
+        } else if r.severity == Severity::Info {
+            Outcome::Skipped(format!("info: {reason}"))
+        } else if r.error {
+            Outcome::Error(reason)
+        } else {
+            Outcome::Failure(reason)
+        };
+        cases.push(TestCase {
+            classname: &r.bucket,
+            name: r.path(),
+            outcome,
+            details: details(r),
+        });
+    }
+
+    for p in sorted_posture(posture) {
+        let outcome = if p.error {
+            Outcome::Error(format!("{} posture check failed", p.check))
+        } else if p.ok {
+            Outcome::Passed
+        } else {
+            Outcome::Failure(format!("{} not satisfied", p.check))
+        };
+        cases.push(TestCase {
+            classname: &p.bucket,
+            name: format!("bucket_checks/{}", p.check),
+            outcome,
+            details: String::new(),
+        });
+    }
+
+    let count = |f: fn(&Outcome) -> bool| cases.iter().filter(|c| f(&c.outcome)).count();
+    let failures = count(|o| matches!(o, Outcome::Failure(_)));
+    let errors = count(|o| matches!(o, Outcome::Error(_)));
+    let skipped = count(|o| matches!(o, Outcome::Skipped(_)));
+    let attributes = format!(
+        "tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" skipped=\"{skipped}\"",
+        cases.len()
+    );
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(out, "<testsuites name=\"s3mon\" {attributes}>");
+    let _ = writeln!(
+        out,
+        "  <testsuite name=\"s3mon\" {attributes} timestamp=\"{}\">",
+        Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+    );
+    for case in &cases {
+        case.write(&mut out);
+    }
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+enum Outcome {
+    Passed,
+    Failure(String),
+    Error(String),
+    Skipped(String),
+}
+
+struct TestCase<'a> {
+    classname: &'a str,
+    name: String,
+    outcome: Outcome,
+    details: String,
+}
+
+impl TestCase<'_> {
+    fn write(&self, out: &mut String) {
+        let _ = write!(
+            out,
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            escape_xml(self.classname),
+            escape_xml(&self.name)
+        );
+        let (element, message) = match &self.outcome {
+            Outcome::Passed => {
+                out.push_str("/>\n");
+                return;
+            }
+            Outcome::Failure(message) => ("failure", message),
+            Outcome::Error(message) => ("error", message),
+            Outcome::Skipped(message) => ("skipped", message),
+        };
+        out.push_str(">\n");
+        if self.details.is_empty() {
+            let _ = writeln!(
+                out,
+                "      <{element} message=\"{}\"/>",
+                escape_xml(message)
+            );
+        } else {
+            let _ = writeln!(
+                out,
+                "      <{element} message=\"{}\">{}</{element}>",
+                escape_xml(message),
+                escape_xml(&self.details)
+            );
+        }
+        out.push_str("    </testcase>\n");
+    }
+}
+
+/// Describe the newest matching object, if any, for the failure body.
+fn details(r: &CheckResult) -> String {
+    r.newest.as_ref().map_or_else(String::new, |newest| {
+        format!(
+            "newest object {} ({} bytes, {}s old), {} matching objects",
+            newest.key,
+            newest.size,
+            Utc::now().timestamp() - newest.last_modified,
+            r.objects
+        )
+    })
+}
+
+/// Escape a string for use in XML attribute values and text.
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::ObjectInfo;
+
+    fn result(prefix: &str, suffix: &str) -> CheckResult {
+        CheckResult {
+            bucket: "bucket_A".to_string(),
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+            exist: true,
+            ..CheckResult::default()
+        }
+    }
+
+    #[test]
+    fn test_format_junit() {
+        let mut missing = result("logs/", ".log");
+        missing.exist = false;
+        missing.newest = Some(ObjectInfo {
+            key: "logs/a&b.log".to_string(),
+            size: 10,
+            last_modified: Utc::now().timestamp() - 90_000,
+            storage_class: None,
+        });
+        let mut error = result("db/", "");
+        error.error = true;
+        error.exist = false;
+        let mut silenced = result("tmp/", "");
+        silenced.size_mismatch = true;
+        silenced.silenced = true;
+        let mut info = result("cache/", "");
+        info.exist = false;
+        info.severity = Severity::Info;
+        // Failed once, short of `fail_after`.
+        let mut pending = result("pending/", "");
+        pending.exist = false;
+        pending.debounced = Some(false);
+        let mut recovering = result("recovering/", "");
+        recovering.debounced = Some(true);
+        let results = vec![
+            result("daily/", ""),
+            missing,
+            error,
+            silenced,
+            info,
+            pending,
+            recovering,
+        ];
+        let posture = vec![PostureResult {
+            bucket: "bucket_A".to_string(),
+            check: "versioning".to_string(),
+            ok: false,
+            error: false,
+        }];
+
+        let out = format_junit(&results, &posture);
+        assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(out.contains(
+            r#"<testsuites name="s3mon" tests="8" failures="3" errors="1" skipped="2">"#
+        ));
+        assert!(out.contains(r#"<testcase classname="bucket_A" name="daily/"/>"#));
+        assert!(out.contains(r#"<testcase classname="bucket_A" name="pending/"/>"#));
+        assert!(out.contains(
+            r#"<testcase classname="bucket_A" name="recovering/">
+      <failure message="recovering"/>"#
+        ));
+        assert!(out.contains(r#"<skipped message="info: missing"/>"#));
+        assert!(out.contains(
+            r#"<testcase classname="bucket_A" name="logs/.log">
+      <failure message="missing">newest object logs/a&amp;b.log (10 bytes, "#
+        ));
+        assert!(out.contains(r#"<error message="error"/>"#));
+        assert!(out.contains(r#"<skipped message="silenced: size mismatch"/>"#));
+        assert!(out.contains(
+            r#"<testcase classname="bucket_A" name="bucket_checks/versioning">
+      <failure message="versioning not satisfied"/>"#
+        ));
+        assert!(out.ends_with("  </testsuite>\n</testsuites>\n"));
+    }
+
+    #[test]
+    fn test_format_junit_empty() {
+        let out = format_junit(&[], &[]);
+        assert!(out.contains(r#"tests="0" failures="0" errors="0" skipped="0""#));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml(r#"<a href="x">&'"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;"
+        );
+    }
+}
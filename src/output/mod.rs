@@ -0,0 +1,1258 @@
+mod graphite;
+mod junit;
+mod nagios;
+mod table;
+
+pub use graphite::{DEFAULT_PATH_TEMPLATE, format_graphite, format_statsd, metric_path};
+pub use junit::format_junit;
+pub use nagios::{format_nagios, format_nagios_unknown};
+pub use table::{format_columns, format_table, human_age, human_size};
+
+use crate::config::Severity;
+use crate::s3::ObjectInfo;
+use chrono::prelude::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// The output format used when printing metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Prometheus text exposition format (default).
+    #[default]
+    Prometheus,
+    /// `InfluxDB` line protocol.
+    Influxdb,
+    /// Nagios / Icinga plugin status line with performance data.
+    Nagios,
+    /// `JUnit` XML report, one test case per check.
+    Junit,
+    /// Aligned, human-readable table for interactive use.
+    Table,
+    /// Graphite plaintext protocol.
+    Graphite,
+    /// `StatsD` gauges.
+    Statsd,
+}
+
+/// Result of a single (bucket, prefix) monitoring check.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default)]
+pub struct CheckResult {
+    pub bucket: String,
+    pub prefix: String,
+    pub suffix: String,
+    /// Child directory under `prefix` for `per_subdirectory` checks, otherwise empty.
+    pub subdir: String,
+    pub exist: bool,
+    pub error: bool,
+    /// Description of the first S3 API error, set together with `error`.
+    pub error_message: Option<String>,
+    pub size_mismatch: bool,
+    /// Outcome of the content validation, `None` when not configured.
+    pub content_valid: Option<bool>,
+    /// Outcome of the sidecar checksum verification, `None` when not configured.
+    pub checksum_mismatch: Option<bool>,
+    /// Compliance rules evaluated for the check, mapped to whether they were violated.
+    pub compliance: BTreeMap<String, bool>,
+    /// Days left until the Object Lock retention of the newest object expires.
+    pub retention_days_remaining: Option<f64>,
+    /// Whether the Object Lock requirements were violated, `None` when not configured.
+    pub retention_violation: Option<bool>,
+    /// Number of objects older than `max_object_age`, `None` when not configured.
+    pub stale_objects: Option<u64>,
+    /// Key and last modification time (Unix seconds) of the oldest matching object.
+    pub oldest: Option<(String, i64)>,
+    /// Most recent scheduled run (Unix seconds) the newest object must be newer than.
+    pub expected: Option<i64>,
+    /// A `silence` entry is active, so failures do not fail the run.
+    pub silenced: bool,
+    pub severity: Severity,
+    /// Most recently modified matching object, regardless of `age`.
+    pub newest: Option<ObjectInfo>,
+    /// Number of matching objects, regardless of `age`.
+    pub objects: u64,
+    /// Last run (Unix seconds) in which the check passed, from the state file.
+    pub last_success: Option<i64>,
+    /// Start (Unix seconds) of the current run of failures, from the state file.
+    pub failing_since: Option<i64>,
+    /// Consecutive failing runs required before the check fails.
+    pub fail_after: u32,
+    /// Consecutive passing runs required before a failing check recovers.
+    pub recover_after: u32,
+    /// Status after applying `fail_after` and `recover_after` to the run
+    /// history, `None` when there is no history.
+    pub debounced: Option<bool>,
+}
+
+impl CheckResult {
+    /// Short descriptions of what failed, empty when the check passed.
+    #[must_use]
+    pub fn failures(&self) -> Vec<String> {
+        let mut failures = vec![];
+        if self.error {
+            failures.push("error".to_string());
+        } else if !self.exist {
+            failures.push("missing".to_string());
+        }
+        if self.size_mismatch {
+            failures.push("size mismatch".to_string());
+        }
+        if self.content_valid == Some(false) {
+            failures.push("invalid content".to_string());
+        }
+        if self.checksum_mismatch == Some(true) {
+            failures.push("checksum mismatch".to_string());
+        }
+        for (rule, violated) in &self.compliance {
+            if *violated {
+                failures.push(format!("{rule} violation"));
+            }
+        }
+        if self.retention_violation == Some(true) {
+            failures.push("retention violation".to_string());
+        }
+        if let Some(count) = self.stale_objects.filter(|count| *count > 0) {
+            failures.push(format!("{count} stale objects"));
+        }
+        failures
+    }
+
+    /// Whether the check is failing: the `debounced` status when there is
+    /// run history, otherwise whether this run had any failure.
+    #[must_use]
+    pub fn is_failing(&self) -> bool {
+        self.debounced
+            .unwrap_or_else(|| !self.failures().is_empty())
+    }
+
+    /// Whether the check failed in a way that affects the exit status, i.e.
+    /// it is neither silenced nor of `info` severity.
+    #[must_use]
+    pub fn counts_as_failure(&self) -> bool {
+        !self.silenced && self.severity > Severity::Info && self.is_failing()
+    }
+
+    /// The checked path: `prefix`, the `subdir` if any, and `suffix`.
+    #[must_use]
+    pub fn path(&self) -> String {
+        if self.subdir.is_empty() {
+            format!("{}{}", self.prefix, self.suffix)
+        } else {
+            format!("{}{}/{}", self.prefix, self.subdir, self.suffix)
+        }
+    }
+}
+
+/// Result of a single bucket-level posture check.
+#[derive(Debug, Default)]
+pub struct PostureResult {
+    pub bucket: String,
+    pub check: String,
+    /// The bucket satisfies the check.
+    pub ok: bool,
+    pub error: bool,
+}
+
+/// Escape a string for use as a Prometheus label value.
+/// Escapes `\`, `"`, and newlines as required by the exposition format spec.
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Escape a string for use as an `InfluxDB` line-protocol string field value.
+/// Escapes backslashes and double quotes.
+fn escape_field(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escape a string for use as an `InfluxDB` line-protocol tag value.
+/// Escapes commas, equals signs, and spaces.
+fn escape_tag(s: &str) -> String {
+    s.replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+use std::fmt::Write as _;
+
+fn prometheus_labels(r: &CheckResult) -> String {
+    let mut labels = format!(
+        "bucket=\"{}\",prefix=\"{}\"",
+        escape_label(&r.bucket),
+        escape_label(&r.prefix),
+    );
+    if !r.suffix.is_empty() {
+        let _ = write!(labels, ",suffix=\"{}\"", escape_label(&r.suffix));
+    }
+    if !r.subdir.is_empty() {
+        let _ = write!(labels, ",subdir=\"{}\"", escape_label(&r.subdir));
+    }
+    if r.silenced {
+        labels.push_str(",silenced=\"true\"");
+    }
+    labels
+}
+
+fn influx_tags(r: &CheckResult) -> String {
+    let mut tags = format!(
+        "bucket={},prefix={}",
+        escape_tag(&r.bucket),
+        escape_tag(&r.prefix),
+    );
+    if !r.suffix.is_empty() {
+        let _ = write!(tags, ",suffix={}", escape_tag(&r.suffix));
+    }
+    if !r.subdir.is_empty() {
+        let _ = write!(tags, ",subdir={}", escape_tag(&r.subdir));
+    }
+    if r.silenced {
+        tags.push_str(",silenced=true");
+    }
+    tags
+}
+
+/// Sort results by (bucket, prefix, suffix, subdir) for deterministic output.
+fn sort_results(results: &[CheckResult]) -> Vec<&CheckResult> {
+    let mut sorted: Vec<&CheckResult> = results.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.bucket
+            .cmp(&b.bucket)
+            .then(a.prefix.cmp(&b.prefix))
+            .then(a.suffix.cmp(&b.suffix))
+            .then(a.subdir.cmp(&b.subdir))
+    });
+    sorted
+}
+
+/// Write a gauge family that only applies to some checks.
+///
+/// The family, including its `# HELP` / `# TYPE` header, is omitted entirely
+/// when `value` returns `None` for every result.
+fn write_optional_family(
+    out: &mut String,
+    sorted: &[&CheckResult],
+    name: &str,
+    help: &str,
+    value: impl Fn(&CheckResult) -> Option<String>,
+) {
+    if sorted.iter().any(|r| value(r).is_some()) {
+        write_family(out, sorted, name, help, value);
+    }
+}
+
+/// Write a gauge family whose series carry an extra label, such as `rule` or `key`.
+///
+/// `series` returns the formatted extra label and the value of each series for
+/// a result; the family is omitted when no result has any series.
+fn write_labelled_family(
+    out: &mut String,
+    sorted: &[&CheckResult],
+    name: &str,
+    help: &str,
+    series: impl Fn(&CheckResult) -> Vec<(String, String)>,
+) {
+    let lines: Vec<String> = sorted
+        .iter()
+        .flat_map(|r| {
+            series(r)
+                .into_iter()
+                .map(move |(label, v)| format!("{name}{{{},{label}}} {v}", prometheus_labels(r)))
+        })
+        .collect();
+    if lines.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for line in lines {
+        let _ = writeln!(out, "{line}");
+    }
+}
+
+/// Write a gauge family with one series per result for which `value` returns a value.
+fn write_family(
+    out: &mut String,
+    sorted: &[&CheckResult],
+    name: &str,
+    help: &str,
+    value: impl Fn(&CheckResult) -> Option<String>,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for r in sorted {
+        if let Some(v) = value(r) {
+            let _ = writeln!(out, "{name}{{{}}} {v}", prometheus_labels(r));
+        }
+    }
+}
+
+/// Format results as Prometheus text exposition format.
+///
+/// All series for a metric family are grouped under a single `# HELP` / `# TYPE`
+/// header, as required by the Prometheus specification.
+/// Results are sorted by (bucket, prefix) for deterministic output.
+#[must_use]
+pub fn format_prometheus(results: &[CheckResult]) -> String {
+    let sorted = sort_results(results);
+
+    let mut out = String::new();
+
+    write_family(
+        &mut out,
+        &sorted,
+        "s3mon_object_exists",
+        "Object exists within the configured age window",
+        |r| Some(i32::from(r.exist).to_string()),
+    );
+
+    write_family(
+        &mut out,
+        &sorted,
+        "s3mon_check_error",
+        "S3 API call failed",
+        |r| Some(i32::from(r.error).to_string()),
+    );
+
+    write_family(
+        &mut out,
+        &sorted,
+        "s3mon_size_mismatch",
+        "Object size is below the configured minimum",
+        |r| Some(i32::from(r.size_mismatch).to_string()),
+    );
+
+    write_labelled_family(
+        &mut out,
+        &sorted,
+        "s3mon_check_severity",
+        "Configured severity of the check",
+        |r| {
+            vec![(
+                format!("severity=\"{}\"", r.severity.as_str()),
+                "1".to_string(),
+            )]
+        },
+    );
+
+    write_newest_object_families(&mut out, &sorted);
+
+    write_optional_family(
+        &mut out,
+        &sorted,
+        "s3mon_stale_objects",
+        "Objects older than the configured max_object_age",
+        |r| r.stale_objects.map(|v| v.to_string()),
+    );
+
+    write_optional_family(
+        &mut out,
+        &sorted,
+        "s3mon_expected_timestamp_seconds",
+        "Most recent scheduled run the newest object must be newer than",
+        |r| r.expected.map(|v| v.to_string()),
+    );
+
+    write_optional_family(
+        &mut out,
+        &sorted,
+        "s3mon_last_success_timestamp_seconds",
+        "Last run in which the check passed",
+        |r| r.last_success.map(|v| v.to_string()),
+    );
+
+    write_optional_family(
+        &mut out,
+        &sorted,
+        "s3mon_check_state",
+        "Check is failing after applying fail_after and recover_after",
+        |r| r.debounced.map(|v| i32::from(v).to_string()),
+    );
+
+    write_optional_family(
+        &mut out,
+        &sorted,
+        "s3mon_failing_since_seconds",
+        "Time the check started failing",
+        |r| r.failing_since.map(|v| v.to_string()),
+    );
+
+    let now = Utc::now().timestamp();
+    write_optional_family(
+        &mut out,
+        &sorted,
+        "s3mon_oldest_object_age_seconds",
+        "Age of the oldest matching object",
+        |r| {
+            r.oldest
+                .as_ref()
+                .map(|(_, last_modified)| (now - last_modified).to_string())
+        },
+    );
+
+    out
+}
+
+/// Write the optional families describing the newest matching object: content,
+/// checksum, compliance and Object Lock retention.
+fn write_newest_object_families(out: &mut String, sorted: &[&CheckResult]) {
+    write_optional_family(
+        out,
+        sorted,
+        "s3mon_content_valid",
+        "Newest object passed content validation",
+        |r| r.content_valid.map(|v| i32::from(v).to_string()),
+    );
+
+    write_optional_family(
+        out,
+        sorted,
+        "s3mon_checksum_mismatch",
+        "Newest object does not match its checksum sidecar",
+        |r| r.checksum_mismatch.map(|v| i32::from(v).to_string()),
+    );
+
+    write_labelled_family(
+        out,
+        sorted,
+        "s3mon_compliance_violation",
+        "Newest object violates a configured compliance rule",
+        |r| {
+            r.compliance
+                .iter()
+                .map(|(rule, violated)| {
+                    (
+                        format!("rule=\"{}\"", escape_label(rule)),
+                        i32::from(*violated).to_string(),
+                    )
+                })
+                .collect()
+        },
+    );
+
+    write_optional_family(
+        out,
+        sorted,
+        "s3mon_retention_days_remaining",
+        "Days left on the Object Lock retention of the newest object",
+        |r| r.retention_days_remaining.map(|v| format!("{v:.2}")),
+    );
+
+    write_optional_family(
+        out,
+        sorted,
+        "s3mon_retention_violation",
+        "Newest object does not meet the Object Lock requirements",
+        |r| r.retention_violation.map(|v| i32::from(v).to_string()),
+    );
+}
+
+/// Timestamp precision of `InfluxDB` lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl Precision {
+    /// `time` as a Unix timestamp in this precision.
+    #[must_use]
+    pub fn timestamp(self, time: DateTime<Utc>) -> i64 {
+        match self {
+            Self::Seconds => time.timestamp(),
+            Self::Milliseconds => time.timestamp_millis(),
+            Self::Microseconds => time.timestamp_micros(),
+            Self::Nanoseconds => time.timestamp_nanos_opt().unwrap_or_default(),
+        }
+    }
+}
+
+/// Optional parts of `InfluxDB` lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InfluxOptions {
+    /// Timestamp appended to every line, already in the chosen precision.
+    pub timestamp: Option<i64>,
+    /// Add `age` (seconds) and `size` (bytes) float fields of the newest
+    /// matching object, as expected by Telegraf's exec input.
+    pub float_fields: bool,
+}
+
+/// Format results as `InfluxDB` line protocol.
+///
+/// Each (bucket, prefix) pair produces one line with three integer fields:
+/// `error`, `exist`, and `size_mismatch`, plus `content_valid`,
+/// `checksum_mismatch`, one `<rule>_violation` field per compliance rule,
+/// `retention_days_remaining`, `retention_violation`, `stale_objects`,
+/// `oldest_age`, `oldest_key`, `expected_timestamp`, `last_success_timestamp`,
+/// `failing_since` and `check_state` when available.
+/// Results are sorted by (bucket, prefix) for deterministic output.
+#[must_use]
+pub fn format_influxdb(results: &[CheckResult]) -> String {
+    format_influxdb_with(results, &InfluxOptions::default())
+}
+
+/// Format results as `InfluxDB` line protocol with the optional `age` and
+/// `size` fields and line timestamp of `options`, see [`format_influxdb`].
+#[must_use]
+pub fn format_influxdb_with(results: &[CheckResult], options: &InfluxOptions) -> String {
+    let sorted = sort_results(results);
+    let now = Utc::now();
+
+    let mut lines: Vec<String> = sorted
+        .iter()
+        .map(|r| {
+            let mut line = format!(
+                "s3mon,{} error={}i,exist={}i,size_mismatch={}i",
+                influx_tags(r),
+                i32::from(r.error),
+                i32::from(r.exist),
+                i32::from(r.size_mismatch),
+            );
+            if let Some(valid) = r.content_valid {
+                let _ = write!(line, ",content_valid={}i", i32::from(valid));
+            }
+            if let Some(mismatch) = r.checksum_mismatch {
+                let _ = write!(line, ",checksum_mismatch={}i", i32::from(mismatch));
+            }
+            for (rule, violated) in &r.compliance {
+                let _ = write!(line, ",{rule}_violation={}i", i32::from(*violated));
+            }
+            if let Some(days) = r.retention_days_remaining {
+                let _ = write!(line, ",retention_days_remaining={days:.2}");
+            }
+            if let Some(violated) = r.retention_violation {
+                let _ = write!(line, ",retention_violation={}i", i32::from(violated));
+            }
+            if let Some(count) = r.stale_objects {
+                let _ = write!(line, ",stale_objects={count}i");
+            }
+            if let Some((key, last_modified)) = &r.oldest {
+                let _ = write!(
+                    line,
+                    ",oldest_age={}i,oldest_key=\"{}\"",
+                    now.timestamp() - last_modified,
+                    escape_field(key),
+                );
+            }
+            if let Some(expected) = r.expected {
+                let _ = write!(line, ",expected_timestamp={expected}i");
+            }
+            if let Some(last_success) = r.last_success {
+                let _ = write!(line, ",last_success_timestamp={last_success}i");
+            }
+            if let Some(failing_since) = r.failing_since {
+                let _ = write!(line, ",failing_since={failing_since}i");
+            }
+            if let Some(debounced) = r.debounced {
+                let _ = write!(line, ",check_state={}i", i32::from(debounced));
+            }
+            if options.float_fields
+                && let Some(newest) = &r.newest
+            {
+                let _ = write!(
+                    line,
+                    ",age={},size={}",
+                    age_seconds(now, newest.last_modified),
+                    float(newest.size),
+                );
+            }
+            if let Some(timestamp) = options.timestamp {
+                let _ = write!(line, " {timestamp}");
+            }
+            line
+        })
+        .collect();
+    lines.push(String::new()); // trailing newline
+    lines.join("\n")
+}
+
+fn sorted_posture(results: &[PostureResult]) -> Vec<&PostureResult> {
+    let mut sorted: Vec<&PostureResult> = results.iter().collect();
+    sorted.sort_by(|a, b| a.bucket.cmp(&b.bucket).then(a.check.cmp(&b.check)));
+    sorted
+}
+
+/// Format bucket posture results as Prometheus text exposition format.
+///
+/// Returns an empty string when no posture checks are configured.
+#[must_use]
+pub fn format_posture_prometheus(results: &[PostureResult]) -> String {
+    if results.is_empty() {
+        return String::new();
+    }
+
+    let sorted = sorted_posture(results);
+    let mut out = String::new();
+
+    out.push_str("# HELP s3mon_bucket_posture Bucket satisfies the posture check\n");
+    out.push_str("# TYPE s3mon_bucket_posture gauge\n");
+    for r in &sorted {
+        let _ = writeln!(
+            out,
+            "s3mon_bucket_posture{{bucket=\"{}\",check=\"{}\"}} {}",
+            escape_label(&r.bucket),
+            escape_label(&r.check),
+            i32::from(r.ok),
+        );
+    }
+
+    out.push_str("# HELP s3mon_bucket_posture_error S3 API call for the posture check failed\n");
+    out.push_str("# TYPE s3mon_bucket_posture_error gauge\n");
+    for r in &sorted {
+        let _ = writeln!(
+            out,
+            "s3mon_bucket_posture_error{{bucket=\"{}\",check=\"{}\"}} {}",
+            escape_label(&r.bucket),
+            escape_label(&r.check),
+            i32::from(r.error),
+        );
+    }
+
+    out
+}
+
+/// Format bucket posture results as `InfluxDB` line protocol, one
+/// `s3mon_bucket` line per (bucket, check) with `ok` and `error` fields.
+#[must_use]
+pub fn format_posture_influxdb(results: &[PostureResult]) -> String {
+    format_posture_influxdb_with(results, &InfluxOptions::default())
+}
+
+/// Format bucket posture results as `InfluxDB` line protocol with the line
+/// timestamp of `options`, see [`format_posture_influxdb`].
+#[must_use]
+pub fn format_posture_influxdb_with(results: &[PostureResult], options: &InfluxOptions) -> String {
+    let mut out = String::new();
+    for r in sorted_posture(results) {
+        let _ = write!(
+            out,
+            "s3mon_bucket,bucket={},check={} error={}i,ok={}i",
+            escape_tag(&r.bucket),
+            escape_tag(&r.check),
+            i32::from(r.error),
+            i32::from(r.ok),
+        );
+        if let Some(timestamp) = options.timestamp {
+            let _ = write!(out, " {timestamp}");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Seconds elapsed between `last_modified` (Unix seconds) and `now`, with
+/// millisecond resolution.
+#[allow(clippy::cast_precision_loss)]
+fn age_seconds(now: DateTime<Utc>, last_modified: i64) -> f64 {
+    (now.timestamp_millis() - last_modified * 1000) as f64 / 1000.0
+}
+
+/// An `InfluxDB` float field value; whole numbers keep a `.0` so the field
+/// type is obvious when reading the line.
+#[allow(clippy::cast_precision_loss)]
+fn float(value: i64) -> String {
+    format!("{:?}", value as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<CheckResult> {
+        vec![
+            CheckResult {
+                bucket: "bucket_B".to_string(),
+                prefix: "foo/".to_string(),
+                suffix: String::new(),
+                exist: false,
+                error: true,
+                size_mismatch: false,
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket_A".to_string(),
+                prefix: "test/".to_string(),
+                suffix: String::new(),
+                exist: true,
+                error: false,
+                size_mismatch: false,
+                ..CheckResult::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_prometheus_sorted_and_grouped() {
+        let out = format_prometheus(&results());
+        // bucket_A should appear before bucket_B after sorting
+        let lines: Vec<&str> = out.lines().collect();
+        let exists_a = lines
+            .iter()
+            .find(|l| l.contains("s3mon_object_exists") && l.contains("bucket_A"))
+            .copied();
+        let exists_b = lines
+            .iter()
+            .find(|l| l.contains("s3mon_object_exists") && l.contains("bucket_B"))
+            .copied();
+        assert_eq!(
+            exists_a,
+            Some(r#"s3mon_object_exists{bucket="bucket_A",prefix="test/"} 1"#)
+        );
+        assert_eq!(
+            exists_b,
+            Some(r#"s3mon_object_exists{bucket="bucket_B",prefix="foo/"} 0"#)
+        );
+    }
+
+    #[test]
+    fn test_prometheus_has_help_and_type_headers() {
+        let out = format_prometheus(&results());
+        assert!(out.contains("# HELP s3mon_object_exists"));
+        assert!(out.contains("# TYPE s3mon_object_exists gauge"));
+        assert!(out.contains("# HELP s3mon_check_error"));
+        assert!(out.contains("# TYPE s3mon_check_error gauge"));
+        assert!(out.contains("# HELP s3mon_size_mismatch"));
+        assert!(out.contains("# TYPE s3mon_size_mismatch gauge"));
+    }
+
+    #[test]
+    fn test_influxdb_format() {
+        let out = format_influxdb(&results());
+        // After sort: bucket_A first
+        let mut lines = out.lines();
+        assert_eq!(
+            lines.next(),
+            Some("s3mon,bucket=bucket_A,prefix=test/ error=0i,exist=1i,size_mismatch=0i")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("s3mon,bucket=bucket_B,prefix=foo/ error=1i,exist=0i,size_mismatch=0i")
+        );
+    }
+
+    #[test]
+    fn test_escape_label_special_chars() {
+        let r = vec![CheckResult {
+            bucket: r#"buck"et"#.to_string(),
+            prefix: "pre\\fix".to_string(),
+            suffix: ".log".to_string(),
+            exist: true,
+            error: false,
+            size_mismatch: false,
+            ..CheckResult::default()
+        }];
+        let out = format_prometheus(&r);
+        assert!(out.contains(r#"bucket="buck\"et""#));
+        assert!(out.contains(r#"prefix="pre\\fix""#));
+        assert!(out.contains(r#"suffix=".log""#));
+    }
+
+    #[test]
+    fn test_sorting_uses_suffix_when_bucket_and_prefix_match() {
+        let results = vec![
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "logs/".to_string(),
+                suffix: ".zst".to_string(),
+                exist: true,
+                error: false,
+                size_mismatch: false,
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "logs/".to_string(),
+                suffix: ".log".to_string(),
+                exist: true,
+                error: false,
+                size_mismatch: false,
+                ..CheckResult::default()
+            },
+        ];
+
+        let out = format_prometheus(&results);
+        let lines: Vec<&str> = out
+            .lines()
+            .filter(|line| line.starts_with("s3mon_object_exists"))
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                r#"s3mon_object_exists{bucket="bucket",prefix="logs/",suffix=".log"} 1"#,
+                r#"s3mon_object_exists{bucket="bucket",prefix="logs/",suffix=".zst"} 1"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_results() {
+        assert_eq!(
+            format_prometheus(&[]),
+            "# HELP s3mon_object_exists Object exists within the configured age window\n\
+             # TYPE s3mon_object_exists gauge\n\
+             # HELP s3mon_check_error S3 API call failed\n\
+             # TYPE s3mon_check_error gauge\n\
+             # HELP s3mon_size_mismatch Object size is below the configured minimum\n\
+             # TYPE s3mon_size_mismatch gauge\n"
+        );
+        assert_eq!(format_influxdb(&[]), "");
+    }
+
+    #[test]
+    fn test_both_formats_encode_identical_values() {
+        // The same logical data must be represented consistently in both formats.
+        let results = vec![
+            CheckResult {
+                bucket: "bucket_A".to_string(),
+                prefix: "daily/".to_string(),
+                suffix: String::new(),
+                exist: true,
+                error: false,
+                size_mismatch: false,
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket_B".to_string(),
+                prefix: "logs/".to_string(),
+                suffix: String::new(),
+                exist: false,
+                error: true,
+                size_mismatch: false,
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket_C".to_string(),
+                prefix: "data/".to_string(),
+                suffix: ".log".to_string(),
+                exist: true,
+                error: false,
+                size_mismatch: true,
+                ..CheckResult::default()
+            },
+        ];
+
+        let prom = format_prometheus(&results);
+        let influx = format_influxdb(&results);
+
+        // bucket_A: exist=1, error=0, size_mismatch=0
+        assert!(prom.contains(r#"s3mon_object_exists{bucket="bucket_A",prefix="daily/"} 1"#));
+        assert!(prom.contains(r#"s3mon_check_error{bucket="bucket_A",prefix="daily/"} 0"#));
+        assert!(prom.contains(r#"s3mon_size_mismatch{bucket="bucket_A",prefix="daily/"} 0"#));
+        assert!(
+            influx
+                .contains("s3mon,bucket=bucket_A,prefix=daily/ error=0i,exist=1i,size_mismatch=0i")
+        );
+
+        // bucket_B: exist=0, error=1, size_mismatch=0
+        assert!(prom.contains(r#"s3mon_object_exists{bucket="bucket_B",prefix="logs/"} 0"#));
+        assert!(prom.contains(r#"s3mon_check_error{bucket="bucket_B",prefix="logs/"} 1"#));
+        assert!(prom.contains(r#"s3mon_size_mismatch{bucket="bucket_B",prefix="logs/"} 0"#));
+        assert!(
+            influx
+                .contains("s3mon,bucket=bucket_B,prefix=logs/ error=1i,exist=0i,size_mismatch=0i")
+        );
+
+        // bucket_C: exist=1, error=0, size_mismatch=1
+        assert!(
+            prom.contains(
+                r#"s3mon_object_exists{bucket="bucket_C",prefix="data/",suffix=".log"} 1"#
+            )
+        );
+        assert!(
+            prom.contains(r#"s3mon_check_error{bucket="bucket_C",prefix="data/",suffix=".log"} 0"#)
+        );
+        assert!(
+            prom.contains(
+                r#"s3mon_size_mismatch{bucket="bucket_C",prefix="data/",suffix=".log"} 1"#
+            )
+        );
+        assert!(influx.contains(
+            "s3mon,bucket=bucket_C,prefix=data/,suffix=.log error=0i,exist=1i,size_mismatch=1i"
+        ));
+    }
+
+    #[test]
+    fn test_content_valid_only_for_configured_checks() {
+        let results = vec![
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "dumps/".to_string(),
+                exist: true,
+                content_valid: Some(false),
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "logs/".to_string(),
+                exist: true,
+                ..CheckResult::default()
+            },
+        ];
+
+        let prom = format_prometheus(&results);
+        assert!(prom.contains("# TYPE s3mon_content_valid gauge"));
+        assert!(prom.contains(r#"s3mon_content_valid{bucket="bucket",prefix="dumps/"} 0"#));
+        assert!(!prom.contains(r#"s3mon_content_valid{bucket="bucket",prefix="logs/"}"#));
+
+        let influx = format_influxdb(&results);
+        assert!(influx.contains(
+            "s3mon,bucket=bucket,prefix=dumps/ error=0i,exist=1i,size_mismatch=0i,content_valid=0i\n"
+        ));
+        assert!(
+            influx
+                .contains("s3mon,bucket=bucket,prefix=logs/ error=0i,exist=1i,size_mismatch=0i\n")
+        );
+    }
+
+    #[test]
+    fn test_checksum_mismatch_only_for_configured_checks() {
+        let results = vec![
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "dumps/".to_string(),
+                exist: true,
+                checksum_mismatch: Some(true),
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "logs/".to_string(),
+                exist: true,
+                ..CheckResult::default()
+            },
+        ];
+
+        let prom = format_prometheus(&results);
+        assert!(prom.contains("# TYPE s3mon_checksum_mismatch gauge"));
+        assert!(prom.contains(r#"s3mon_checksum_mismatch{bucket="bucket",prefix="dumps/"} 1"#));
+        assert!(!prom.contains(r#"s3mon_checksum_mismatch{bucket="bucket",prefix="logs/"}"#));
+
+        let influx = format_influxdb(&results);
+        assert!(influx.contains(
+            "s3mon,bucket=bucket,prefix=dumps/ error=0i,exist=1i,size_mismatch=0i,checksum_mismatch=1i\n"
+        ));
+    }
+
+    #[test]
+    fn test_compliance_violation_per_rule() {
+        let results = vec![CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "dumps/".to_string(),
+            exist: true,
+            compliance: BTreeMap::from([
+                ("encryption".to_string(), false),
+                ("storage_class".to_string(), true),
+            ]),
+            ..CheckResult::default()
+        }];
+
+        let prom = format_prometheus(&results);
+        assert!(prom.contains("# TYPE s3mon_compliance_violation gauge"));
+        assert!(prom.contains(
+            r#"s3mon_compliance_violation{bucket="bucket",prefix="dumps/",rule="encryption"} 0"#
+        ));
+        assert!(prom.contains(
+            r#"s3mon_compliance_violation{bucket="bucket",prefix="dumps/",rule="storage_class"} 1"#
+        ));
+
+        let influx = format_influxdb(&results);
+        assert!(influx.contains(
+            "s3mon,bucket=bucket,prefix=dumps/ error=0i,exist=1i,size_mismatch=0i,encryption_violation=0i,storage_class_violation=1i\n"
+        ));
+        assert!(!format_prometheus(&[]).contains("s3mon_compliance_violation"));
+    }
+
+    #[test]
+    fn test_retention_metrics() {
+        let results = vec![
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "locked/".to_string(),
+                exist: true,
+                retention_days_remaining: Some(12.5),
+                retention_violation: Some(true),
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "unlocked/".to_string(),
+                exist: true,
+                retention_violation: Some(true),
+                ..CheckResult::default()
+            },
+        ];
+
+        let prom = format_prometheus(&results);
+        assert!(
+            prom.contains(
+                r#"s3mon_retention_days_remaining{bucket="bucket",prefix="locked/"} 12.50"#
+            )
+        );
+        assert!(
+            !prom.contains(r#"s3mon_retention_days_remaining{bucket="bucket",prefix="unlocked/"}"#)
+        );
+        assert!(prom.contains(r#"s3mon_retention_violation{bucket="bucket",prefix="locked/"} 1"#));
+        assert!(
+            prom.contains(r#"s3mon_retention_violation{bucket="bucket",prefix="unlocked/"} 1"#)
+        );
+
+        let influx = format_influxdb(&results);
+        assert!(influx.contains(
+            "s3mon,bucket=bucket,prefix=locked/ error=0i,exist=1i,size_mismatch=0i,retention_days_remaining=12.50,retention_violation=1i\n"
+        ));
+    }
+
+    #[test]
+    fn test_posture_formats() {
+        let results = vec![
+            PostureResult {
+                bucket: "bucket_B".to_string(),
+                check: "versioning".to_string(),
+                ok: false,
+                error: true,
+            },
+            PostureResult {
+                bucket: "bucket_A".to_string(),
+                check: "versioning".to_string(),
+                ok: true,
+                error: false,
+            },
+        ];
+
+        let prom = format_posture_prometheus(&results);
+        let lines: Vec<&str> = prom
+            .lines()
+            .filter(|line| line.starts_with("s3mon_bucket_posture{"))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"s3mon_bucket_posture{bucket="bucket_A",check="versioning"} 1"#,
+                r#"s3mon_bucket_posture{bucket="bucket_B",check="versioning"} 0"#,
+            ]
+        );
+        assert!(
+            prom.contains(r#"s3mon_bucket_posture_error{bucket="bucket_B",check="versioning"} 1"#)
+        );
+
+        assert_eq!(
+            format_posture_influxdb(&results),
+            "s3mon_bucket,bucket=bucket_A,check=versioning error=0i,ok=1i\n\
+             s3mon_bucket,bucket=bucket_B,check=versioning error=1i,ok=0i\n"
+        );
+        assert_eq!(format_posture_prometheus(&[]), "");
+        assert_eq!(format_posture_influxdb(&[]), "");
+    }
+
+    #[test]
+    fn test_subdir_label() {
+        let results = vec![
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "tenants/".to_string(),
+                subdir: "beta".to_string(),
+                exist: false,
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "tenants/".to_string(),
+                subdir: "alpha".to_string(),
+                exist: true,
+                ..CheckResult::default()
+            },
+        ];
+
+        let prom = format_prometheus(&results);
+        let lines: Vec<&str> = prom
+            .lines()
+            .filter(|line| line.starts_with("s3mon_object_exists"))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"s3mon_object_exists{bucket="bucket",prefix="tenants/",subdir="alpha"} 1"#,
+                r#"s3mon_object_exists{bucket="bucket",prefix="tenants/",subdir="beta"} 0"#,
+            ]
+        );
+
+        let influx = format_influxdb(&results);
+        assert!(influx.starts_with(
+            "s3mon,bucket=bucket,prefix=tenants/,subdir=alpha error=0i,exist=1i,size_mismatch=0i\n"
+        ));
+    }
+
+    #[test]
+    fn test_stale_objects_metrics() {
+        let last_modified = Utc::now().timestamp() - 100;
+        let results = vec![CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "logs/".to_string(),
+            exist: true,
+            stale_objects: Some(3),
+            oldest: Some(("logs/a \"b\".log".to_string(), last_modified)),
+            ..CheckResult::default()
+        }];
+
+        // The age is computed when formatting, which may cross a second.
+        let ages = [100, 101];
+        let prom = format_prometheus(&results);
+        let lines: Vec<&str> = prom.lines().filter(|l| !l.starts_with('#')).collect();
+        assert!(lines.contains(&r#"s3mon_stale_objects{bucket="bucket",prefix="logs/"} 3"#));
+        assert!(ages.iter().any(|age| {
+            lines.contains(
+                &format!(
+                    r#"s3mon_oldest_object_age_seconds{{bucket="bucket",prefix="logs/"}} {age}"#
+                )
+                .as_str(),
+            )
+        }));
+
+        let influx = format_influxdb(&results);
+        assert!(ages.iter().any(|age| influx.contains(&format!(
+            ",stale_objects=3i,oldest_age={age}i,oldest_key=\"logs/a \\\"b\\\".log\"\n"
+        ))));
+        assert!(!format_prometheus(&[]).contains("s3mon_stale_objects"));
+    }
+
+    #[test]
+    fn test_state_metrics() {
+        let results = vec![
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "db/".to_string(),
+                exist: true,
+                last_success: Some(1_704_420_000),
+                ..CheckResult::default()
+            },
+            CheckResult {
+                bucket: "bucket".to_string(),
+                prefix: "logs/".to_string(),
+                last_success: Some(1_704_400_000),
+                failing_since: Some(1_704_410_000),
+                ..CheckResult::default()
+            },
+        ];
+
+        let prom = format_prometheus(&results);
+        assert!(prom.contains(
+            r#"s3mon_last_success_timestamp_seconds{bucket="bucket",prefix="db/"} 1704420000"#
+        ));
+        assert!(
+            prom.contains(
+                r#"s3mon_failing_since_seconds{bucket="bucket",prefix="logs/"} 1704410000"#
+            )
+        );
+        assert!(!prom.contains(r#"s3mon_failing_since_seconds{bucket="bucket",prefix="db/"}"#));
+
+        let influx = format_influxdb(&results);
+        assert!(influx.contains(",last_success_timestamp=1704400000i,failing_since=1704410000i"));
+        assert!(!format_prometheus(&[]).contains("s3mon_last_success_timestamp_seconds"));
+    }
+
+    #[test]
+    fn test_expected_timestamp_metrics() {
+        let results = vec![CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "db/".to_string(),
+            exist: true,
+            expected: Some(1_704_420_000),
+            ..CheckResult::default()
+        }];
+        assert!(format_prometheus(&results).contains(
+            r#"s3mon_expected_timestamp_seconds{bucket="bucket",prefix="db/"} 1704420000"#
+        ));
+        assert!(format_influxdb(&results).contains(",expected_timestamp=1704420000i"));
+        assert!(!format_prometheus(&[]).contains("s3mon_expected_timestamp_seconds"));
+    }
+
+    #[test]
+    fn test_silenced_label() {
+        let results = vec![CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "db/".to_string(),
+            silenced: true,
+            ..CheckResult::default()
+        }];
+        assert!(
+            format_prometheus(&results)
+                .contains(r#"s3mon_object_exists{bucket="bucket",prefix="db/",silenced="true"} 0"#)
+        );
+        assert!(
+            format_influxdb(&results).contains("s3mon,bucket=bucket,prefix=db/,silenced=true ")
+        );
+    }
+
+    #[test]
+    fn test_check_severity() {
+        let results = vec![CheckResult {
+            bucket: "bucket".to_string(),
+            prefix: "logs/".to_string(),
+            severity: Severity::Warning,
+            ..CheckResult::default()
+        }];
+        assert!(format_prometheus(&results).contains(
+            r#"s3mon_check_severity{bucket="bucket",prefix="logs/",severity="warning"} 1"#
+        ));
+    }
+
+    #[test]
+    fn test_influxdb_timestamp_and_float_fields() {
+        let started = DateTime::parse_from_rfc3339("2025-10-18T02:00:00.123456789Z")
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_default();
+        assert_eq!(Precision::Seconds.timestamp(started), 1_760_752_800);
+        assert_eq!(
+            Precision::Milliseconds.timestamp(started),
+            1_760_752_800_123
+        );
+        assert_eq!(
+            Precision::Microseconds.timestamp(started),
+            1_760_752_800_123_456
+        );
+        assert_eq!(
+            Precision::Nanoseconds.timestamp(started),
+            1_760_752_800_123_456_789
+        );
+
+        let mut results = results();
+        if let Some(r) = results.get_mut(1) {
+            r.newest = Some(ObjectInfo {
+                key: "test/dump.sql.gz".to_string(),
+                size: 2048,
+                last_modified: Utc::now().timestamp() - 60,
+                storage_class: None,
+            });
+        }
+        let options = InfluxOptions {
+            timestamp: Some(1_760_752_800),
+            float_fields: true,
+        };
+        let out = format_influxdb_with(&results, &options);
+        let mut lines = out.lines();
+        let first = lines.next().unwrap_or_default();
+        assert!(first.starts_with(
+            "s3mon,bucket=bucket_A,prefix=test/ error=0i,exist=1i,size_mismatch=0i,age=6"
+        ));
+        assert!(first.ends_with(",size=2048.0 1760752800"));
+        assert_eq!(
+            lines.next(),
+            Some("s3mon,bucket=bucket_B,prefix=foo/ error=1i,exist=0i,size_mismatch=0i 1760752800")
+        );
+
+        let posture = vec![PostureResult {
+            bucket: "bucket_A".to_string(),
+            check: "versioning".to_string(),
+            ok: true,
+            error: false,
+        }];
+        assert_eq!(
+            format_posture_influxdb_with(&posture, &options),
+            "s3mon_bucket,bucket=bucket_A,check=versioning error=0i,ok=1i 1760752800\n"
+        );
+        assert_eq!(float(3), "3.0");
+    }
+}
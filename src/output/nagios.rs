@@ -0,0 +1,171 @@
+use super::{CheckResult, PostureResult, sort_results, sorted_posture};
+use crate::config::Severity;
+use chrono::prelude::Utc;
+use std::fmt::Write as _;
+
+/// Format results as a Nagios / Icinga plugin status line with performance data.
+///
+/// The state is `CRITICAL` or `WARNING` after the most severe failing check
+/// (failing posture checks count as critical), otherwise `OK`.  Silenced and
+/// `info` checks never change the state.  Every check contributes `age`
+/// (seconds since the newest matching object), `size` (of that object) and
+/// `count` (matching objects) to the performance data.
+#[must_use]
+pub fn format_nagios(results: &[CheckResult], posture: &[PostureResult]) -> String {
+    let sorted = sort_results(results);
+    let posture = sorted_posture(posture);
+
+    let mut failed: Vec<String> = sorted
+        .iter()
+        .filter(|r| r.counts_as_failure())
+        .map(|r| {
+            let failures = r.failures();
+            let reason = if failures.is_empty() {
+                // Passed, but not yet `recover_after` times in a row.
+                "recovering".to_string()
+            } else {
+                failures.join(", ")
+            };
+            format!("{}/{} {reason}", r.bucket, r.path())
+        })
+        .collect();
+    let mut worst = sorted
+        .iter()
+        .filter(|r| r.counts_as_failure())
+        .map(|r| r.severity)
+        .max();
+
+    for p in posture.iter().filter(|p| p.error || !p.ok) {
+        let reason = if p.error { "error" } else { "failed" };
+        failed.push(format!("{} {} {reason}", p.bucket, p.check));
+        worst = Some(Severity::Critical);
+    }
+
+    let total = sorted.len() + posture.len();
+    let state = match worst {
+        Some(Severity::Critical) => "CRITICAL",
+        Some(Severity::Warning) => "WARNING",
+        _ => "OK",
+    };
+
+    let mut out = if failed.is_empty() {
+        format!("S3MON {state} - {total} checks passed")
+    } else {
+        format!(
+            "S3MON {state} - {} of {total} checks failed: {}",
+            failed.len(),
+            failed.join("; ")
+        )
+    }
+    .replace('|', "/");
+
+    let now = Utc::now().timestamp();
+    let mut perfdata = vec![];
+    for r in &sorted {
+        let label = perf_label(&format!("{}/{}", r.bucket, r.path()));
+        if let Some(newest) = &r.newest {
+            perfdata.push(format!("'{label} age'={}s", now - newest.last_modified));
+            perfdata.push(format!("'{label} size'={}B", newest.size));
+        }
+        perfdata.push(format!("'{label} count'={}", r.objects));
+    }
+    if !perfdata.is_empty() {
+        let _ = write!(out, " | {}", perfdata.join(" "));
+    }
+    out.push('\n');
+    out
+}
+
+/// Status line for an error that prevented the checks from running.
+#[must_use]
+pub fn format_nagios_unknown(error: &anyhow::Error) -> String {
+    format!("S3MON UNKNOWN - {}\n", error.to_string().replace('|', "/"))
+}
+
+/// Escape a performance data label: single quotes are doubled and `=`,
+/// which separates the label from the value, is replaced.
+fn perf_label(label: &str) -> String {
+    label.replace('\'', "''").replace('=', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::ObjectInfo;
+
+    fn result(bucket: &str, prefix: &str, exist: bool) -> CheckResult {
+        CheckResult {
+            bucket: bucket.to_string(),
+            prefix: prefix.to_string(),
+            exist,
+            ..CheckResult::default()
+        }
+    }
+
+    #[test]
+    fn test_format_nagios_ok() {
+        let mut fresh = result("bucket_A", "db/", true);
+        fresh.objects = 3;
+        fresh.newest = Some(ObjectInfo {
+            key: "db/dump.sql.gz".to_string(),
+            size: 2048,
+            last_modified: Utc::now().timestamp() - 60,
+            storage_class: None,
+        });
+
+        let out = format_nagios(&[fresh], &[]);
+        assert!(out.starts_with("S3MON OK - 1 checks passed | 'bucket_A/db/ age'="));
+        assert!(out.ends_with("s 'bucket_A/db/ size'=2048B 'bucket_A/db/ count'=3\n"));
+    }
+
+    #[test]
+    fn test_format_nagios_failures() {
+        let mut warning = result("bucket_A", "logs/", true);
+        warning.size_mismatch = true;
+        warning.severity = Severity::Warning;
+        let mut silenced = result("bucket_C", "tmp/", false);
+        silenced.silenced = true;
+        let results = vec![
+            result("bucket_B", "logs/", false),
+            warning,
+            result("bucket_A", "db/", true),
+            silenced,
+        ];
+
+        assert_eq!(
+            format_nagios(&results, &[]),
+            "S3MON CRITICAL - 2 of 4 checks failed: bucket_A/logs/ size mismatch; \
+             bucket_B/logs/ missing | 'bucket_A/db/ count'=0 'bucket_A/logs/ count'=0 \
+             'bucket_B/logs/ count'=0 'bucket_C/tmp/ count'=0\n"
+        );
+
+        let out = format_nagios(results.get(1..2).unwrap_or_default(), &[]);
+        assert!(
+            out.starts_with("S3MON WARNING - 1 of 1 checks failed: bucket_A/logs/ size mismatch |")
+        );
+    }
+
+    #[test]
+    fn test_format_nagios_posture() {
+        let posture = vec![PostureResult {
+            bucket: "bucket_A".to_string(),
+            check: "versioning".to_string(),
+            ok: false,
+            error: false,
+        }];
+        assert_eq!(
+            format_nagios(&[], &posture),
+            "S3MON CRITICAL - 1 of 1 checks failed: bucket_A versioning failed\n"
+        );
+    }
+
+    #[test]
+    fn test_format_nagios_unknown() {
+        let error = anyhow::anyhow!("error parsing config: a | b");
+        assert_eq!(
+            format_nagios_unknown(&error),
+            "S3MON UNKNOWN - error parsing config: a / b\n"
+        );
+        assert_eq!(perf_label("it's a=b"), "it''s a_b");
+    }
+}
@@ -0,0 +1,275 @@
+use super::{CheckResult, PostureResult, sort_results, sorted_posture};
+use chrono::prelude::Utc;
+use clap::builder::styling::{AnsiColor, Effects, Style};
+use std::fmt::Write as _;
+
+const HEADER: [&str; 6] = ["BUCKET", "PREFIX", "STATUS", "NEWEST", "SIZE", "ERROR"];
+
+/// Format results as an aligned, human-readable table.
+///
+/// The status column is colored with ANSI escape codes; print the table
+/// through [`anstream`] so they are stripped when colors are disabled, e.g.
+/// when stdout is not a terminal.
+#[must_use]
+pub fn format_table(results: &[CheckResult], posture: &[PostureResult]) -> String {
+    let now = Utc::now().timestamp();
+
+    let mut rows: Vec<([String; 6], Style)> = sort_results(results)
+        .into_iter()
+        .map(|r| {
+            let (status, style) = status(r);
+            let (newest, size) = r.newest.as_ref().map_or_else(
+                || ("-".to_string(), "-".to_string()),
+                |n| (human_age(now - n.last_modified), human_size(n.size)),
+            );
+            let failures = r.failures();
+            let error = if !failures.is_empty() {
+                failures.join(", ")
+            } else if r.is_failing() {
+                // Passed, but not yet `recover_after` times in a row.
+                "recovering".to_string()
+            } else {
+                "-".to_string()
+            };
+            (
+                [
+                    r.bucket.clone(),
+                    r.path(),
+                    status.to_string(),
+                    newest,
+                    size,
+                    error,
+                ],
+                style,
+            )
+        })
+        .collect();
+
+    rows.extend(sorted_posture(posture).into_iter().map(|p| {
+        let (status, style, error) = if p.error {
+            ("FAIL", red(), "error")
+        } else if p.ok {
+            ("OK", green(), "-")
+        } else {
+            ("FAIL", red(), "not satisfied")
+        };
+        (
+            [
+                p.bucket.clone(),
+                format!("[{}]", p.check),
+                status.to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                error.to_string(),
+            ],
+            style,
+        )
+    }));
+
+    let mut widths = HEADER.map(str::len);
+    for (row, _) in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let bold = Style::new().effects(Effects::BOLD);
+    let mut out = String::new();
+    write_row(&mut out, &HEADER.map(str::to_string), &widths, |_| bold);
+    for (row, style) in &rows {
+        write_row(&mut out, row, &widths, |column| {
+            if column == 2 { *style } else { Style::new() }
+        });
+    }
+    out
+}
+
+/// Align `rows` in plain columns under a bold `header`.
+#[must_use]
+pub fn format_columns(header: &[&str], rows: &[Vec<String>]) -> String {
+    let header: Vec<String> = header.iter().map(ToString::to_string).collect();
+    let mut widths: Vec<usize> = header.iter().map(String::len).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let bold = Style::new().effects(Effects::BOLD);
+    let mut out = String::new();
+    write_row(&mut out, &header, &widths, |_| bold);
+    for row in rows {
+        write_row(&mut out, row, &widths, |_| Style::new());
+    }
+    out
+}
+
+/// Write one row, padding every cell but the last to the column width.
+fn write_row(out: &mut String, row: &[String], widths: &[usize], style: impl Fn(usize) -> Style) {
+    let last = row.len().saturating_sub(1);
+    for (column, (cell, width)) in row.iter().zip(widths).enumerate() {
+        let s = style(column);
+        if column == last {
+            let _ = writeln!(out, "{s}{cell}{s:#}");
+        } else {
+            let padding = width - cell.chars().count() + 2;
+            let _ = write!(out, "{s}{cell}{s:#}{:padding$}", "");
+        }
+    }
+}
+
+/// Status label and color of a check, after `fail_after` and `recover_after`.
+///
+/// Only checks that affect the exit status are shown as `FAIL` or `WARN`.
+fn status(r: &CheckResult) -> (&'static str, Style) {
+    if r.counts_as_failure() {
+        if r.severity == crate::config::Severity::Critical {
+            ("FAIL", red())
+        } else {
+            ("WARN", AnsiColor::Yellow.on_default())
+        }
+    } else if !r.is_failing() {
+        ("OK", green())
+    } else if r.silenced {
+        ("SILENCED", Style::new().effects(Effects::DIMMED))
+    } else {
+        ("INFO", AnsiColor::Blue.on_default())
+    }
+}
+
+fn green() -> Style {
+    AnsiColor::Green.on_default()
+}
+
+fn red() -> Style {
+    AnsiColor::Red.on_default() | Effects::BOLD
+}
+
+/// Render an age in seconds with its two most significant units, e.g. `2d 3h`.
+#[must_use]
+pub fn human_age(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let units = [
+        (seconds / 86_400, "d"),
+        (seconds / 3_600 % 24, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+    let parts: Vec<String> = units
+        .iter()
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{value}{unit}"))
+        .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Render a size in bytes with binary units, e.g. `1.5 MiB`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn human_size(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+    use crate::s3::ObjectInfo;
+
+    #[test]
+    fn test_format_table() {
+        let fresh = CheckResult {
+            bucket: "bucket_A".to_string(),
+            prefix: "daily/".to_string(),
+            exist: true,
+            newest: Some(ObjectInfo {
+                key: "daily/dump.sql.gz".to_string(),
+                size: 3 * 1024 * 1024 / 2,
+                last_modified: Utc::now().timestamp() - 7_500,
+                storage_class: None,
+            }),
+            ..CheckResult::default()
+        };
+        let missing = CheckResult {
+            bucket: "bucket_B".to_string(),
+            prefix: "logs/".to_string(),
+            severity: Severity::Warning,
+            ..CheckResult::default()
+        };
+        let posture = vec![PostureResult {
+            bucket: "bucket_A".to_string(),
+            check: "versioning".to_string(),
+            ok: true,
+            error: false,
+        }];
+
+        let out = format_table(&[missing, fresh], &posture);
+        let plain = anstream::adapter::strip_str(&out).to_string();
+        assert_eq!(
+            plain,
+            "BUCKET    PREFIX        STATUS  NEWEST  SIZE     ERROR\n\
+             bucket_A  daily/        OK      2h 5m   1.5 MiB  -\n\
+             bucket_B  logs/         WARN    -       -        missing\n\
+             bucket_A  [versioning]  OK      -       -        -\n"
+        );
+        assert!(out.contains(&format!(
+            "{}WARN{:#}",
+            AnsiColor::Yellow.on_default(),
+            AnsiColor::Yellow.on_default()
+        )));
+    }
+
+    #[test]
+    fn test_status() {
+        let mut r = CheckResult::default();
+        assert_eq!(status(&r).0, "FAIL");
+        r.silenced = true;
+        assert_eq!(status(&r).0, "SILENCED");
+        r.exist = true;
+        assert_eq!(status(&r).0, "OK");
+
+        let mut r = CheckResult {
+            severity: Severity::Info,
+            ..CheckResult::default()
+        };
+        assert_eq!(status(&r).0, "INFO");
+        r.severity = Severity::Warning;
+        assert_eq!(status(&r).0, "WARN");
+        // Short of `fail_after`, then short of `recover_after`.
+        r.debounced = Some(false);
+        assert_eq!(status(&r).0, "OK");
+        r.exist = true;
+        r.debounced = Some(true);
+        assert_eq!(status(&r).0, "WARN");
+    }
+
+    #[test]
+    fn test_human_units() {
+        assert_eq!(human_age(-5), "0s");
+        assert_eq!(human_age(42), "42s");
+        assert_eq!(human_age(3_600), "1h");
+        assert_eq!(human_age(90_061), "1d 1h");
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(2048), "2.0 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
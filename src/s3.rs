@@ -1,18 +1,171 @@
 use crate::config;
 use anyhow::Result;
 use aws_credential_types::Credentials;
+use aws_credential_types::provider::SharedCredentialsProvider;
 use aws_sdk_s3::Client;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::get_object::GetObjectError;
 use aws_smithy_http_client::Builder as HttpClientBuilder;
 use aws_smithy_http_client::tls;
+use base64::Engine as _;
 use chrono::prelude::Utc;
+use sha2::{Digest, Sha256};
 
 pub struct Monitor {
     pub s3: Client,
+    /// Provider the credentials of `s3` come from, for `s3mon doctor`.
+    pub credentials: Option<SharedCredentialsProvider>,
+    /// Whether `s3` forces path-style addressing (`endpoint/bucket/key`), as
+    /// it does for custom endpoints; AWS uses virtual-hosted style.
+    pub path_style: bool,
 }
 
 pub struct CheckStats {
     pub exists: bool,
     pub any_large_enough: bool,
+    /// Most recently modified matching object listed, regardless of the age
+    /// window. It lies within the window exactly when `exists` is set.
+    pub newest: Option<ObjectInfo>,
+    /// Number of matching objects listed, regardless of the age window.
+    pub count: u64,
+    /// Objects older than [`ObjectFilter::stale_cutoff`], when set.
+    pub stale: Option<StaleStats>,
+}
+
+#[derive(Debug, Default)]
+pub struct StaleStats {
+    /// Number of matching objects older than the threshold.
+    pub count: u64,
+    /// Oldest matching object, regardless of the threshold.
+    pub oldest: Option<ObjectInfo>,
+}
+
+/// Listing details of a single S3 object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub size: i64,
+    /// Last modification time as a Unix timestamp in seconds.
+    pub last_modified: i64,
+    pub storage_class: Option<String>,
+}
+
+/// The filters [`Monitor::check_storage`] applies to each listed object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectFilter {
+    pub suffix: String,
+    /// Objects modified after this Unix timestamp are fresh.
+    pub cutoff: i64,
+    /// Minimum size of a fresh object; `0` disables the size check.
+    pub min_size: i64,
+    /// Objects modified before this Unix timestamp are stale; `None` disables
+    /// the stale count.
+    pub stale_cutoff: Option<i64>,
+}
+
+/// Unix timestamp `age` seconds ago.
+fn cutoff(age: i64, name: &str) -> Result<i64> {
+    let age = chrono::Duration::try_seconds(age)
+        .ok_or_else(|| anyhow::anyhow!("invalid {name} value: {age}"))?;
+    Ok((Utc::now() - age).timestamp())
+}
+
+impl ObjectFilter {
+    /// Filter for objects ending in `suffix` that are newer than `age`
+    /// seconds and at least `min_size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `age` is out of range.
+    pub fn new(suffix: &str, age: i64, min_size: i64) -> Result<Self> {
+        Ok(Self {
+            suffix: suffix.to_string(),
+            cutoff: cutoff(age, "age")?,
+            min_size,
+            stale_cutoff: None,
+        })
+    }
+
+    /// Also count the objects older than `max_age` seconds; `0` disables it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `max_age` is out of range.
+    pub fn with_max_object_age(mut self, max_age: i64) -> Result<Self> {
+        if max_age > 0 {
+            self.stale_cutoff = Some(cutoff(max_age, "max_object_age")?);
+        }
+        Ok(self)
+    }
+
+    #[must_use]
+    pub fn matches_suffix(&self, key: &str) -> bool {
+        self.suffix.is_empty() || key.ends_with(&self.suffix)
+    }
+
+    #[must_use]
+    pub const fn is_fresh(&self, last_modified: i64) -> bool {
+        last_modified > self.cutoff
+    }
+
+    #[must_use]
+    pub fn is_large_enough(&self, size: Option<i64>) -> bool {
+        self.min_size <= 0 || size.is_some_and(|s| s >= self.min_size)
+    }
+
+    #[must_use]
+    pub fn is_stale(&self, last_modified: i64) -> bool {
+        self.stale_cutoff
+            .is_some_and(|cutoff| last_modified < cutoff)
+    }
+}
+
+/// Object Lock retention applied to a single object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retention {
+    pub mode: String,
+    /// Retain-until date as a Unix timestamp in seconds.
+    pub retain_until: i64,
+}
+
+/// Error codes returned when an object or its bucket has no Object Lock settings.
+const NO_OBJECT_LOCK: &[&str] = &[
+    "NoSuchObjectLockConfiguration",
+    "ObjectLockConfigurationNotFoundError",
+];
+
+/// Error codes returned when a bucket-level setting has never been configured.
+const NOT_CONFIGURED: &[&str] = &[
+    "NoSuchPublicAccessBlockConfiguration",
+    "ServerSideEncryptionConfigurationNotFoundError",
+    "NoSuchLifecycleConfiguration",
+    "ReplicationConfigurationNotFoundError",
+];
+
+/// Map a "not configured" API error to `false`, keeping any other error.
+fn not_configured<E: ProvideErrorMetadata + std::fmt::Display>(e: &E) -> Result<bool> {
+    if e.code().is_some_and(|code| NOT_CONFIGURED.contains(&code)) {
+        Ok(false)
+    } else {
+        Err(anyhow::anyhow!("{e}"))
+    }
+}
+
+/// Describe a failed S3 call with its HTTP status and error code, e.g.
+/// `403 AccessDenied: Access Denied`.
+fn describe_error<E>(e: &SdkError<E, HttpResponse>) -> String
+where
+    E: ProvideErrorMetadata + std::error::Error + 'static,
+{
+    let status = e.raw_response().map(|r| r.status().as_u16());
+    match (status, e.code()) {
+        (Some(status), Some(code)) => {
+            format!("{status} {code}: {}", e.message().unwrap_or_default())
+        }
+        (Some(status), None) => format!("HTTP {status}"),
+        _ => DisplayErrorContext(e).to_string(),
+    }
 }
 
 impl Monitor {
@@ -56,7 +209,8 @@ impl Monitor {
 
         let mut s3_builder = aws_sdk_s3::Config::from(&aws_cfg).to_builder();
 
-        if !config.s3mon.endpoint.is_empty() {
+        let path_style = !config.s3mon.endpoint.is_empty();
+        if path_style {
             s3_builder = s3_builder
                 .endpoint_url(&config.s3mon.endpoint)
                 .force_path_style(true);
@@ -64,10 +218,14 @@ impl Monitor {
 
         Ok(Self {
             s3: Client::from_conf(s3_builder.build()),
+            credentials: aws_cfg.credentials_provider(),
+            path_style,
         })
     }
 
-    /// Check objects in `bucket` under `prefix` that are newer than `age` seconds.
+    /// Check objects in `bucket` under `prefix` that are newer than `age`
+    /// seconds, stopping at the first page with a fresh object of at least
+    /// `min_size` bytes.
     ///
     /// # Errors
     ///
@@ -80,13 +238,35 @@ impl Monitor {
         age: i64,
         min_size: i64,
     ) -> Result<CheckStats> {
-        let cutoff = (Utc::now()
-            - chrono::Duration::try_seconds(age)
-                .ok_or_else(|| anyhow::anyhow!("invalid age value: {age}"))?)
-        .timestamp();
+        let filter = ObjectFilter::new(suffix, age, min_size)?;
+        self.scan(bucket, prefix, &filter, false).await
+    }
 
-        let mut exists = false;
-        let mut any_large_enough = false;
+    /// List `bucket` under `prefix` and apply `filter` to every object.
+    ///
+    /// Keys are listed lexicographically, so the newest object and the stale
+    /// count take every page.  Unless `full` is set or `filter` counts stale
+    /// objects, listing stops at the first page with a fresh object that is
+    /// large enough, and `newest` and `count` only cover the pages listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails.
+    pub async fn scan(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        filter: &ObjectFilter,
+        full: bool,
+    ) -> Result<CheckStats> {
+        let full = full || filter.stale_cutoff.is_some();
+        let mut stats = CheckStats {
+            exists: false,
+            any_large_enough: false,
+            newest: None,
+            count: 0,
+            stale: filter.stale_cutoff.map(|_| StaleStats::default()),
+        };
 
         let mut paginator = self
             .s3
@@ -99,26 +279,440 @@ impl Monitor {
         while let Some(page) = paginator.next().await {
             let page = page.map_err(|e| anyhow::anyhow!("{e}"))?;
             for obj in page.contents() {
-                let matches_suffix =
-                    suffix.is_empty() || obj.key().is_some_and(|key| key.ends_with(suffix));
+                let Some(last_modified) = obj
+                    .last_modified()
+                    .map(aws_sdk_s3::primitives::DateTime::secs)
+                else {
+                    continue;
+                };
+                let key = obj.key().unwrap_or_default();
+                if !filter.matches_suffix(key) {
+                    continue;
+                }
+                stats.count += 1;
+                let info = || ObjectInfo {
+                    key: key.to_string(),
+                    size: obj.size().unwrap_or_default(),
+                    last_modified,
+                    storage_class: obj.storage_class().map(|c| c.as_str().to_string()),
+                };
 
-                if matches_suffix && obj.last_modified().is_some_and(|lm| lm.secs() > cutoff) {
-                    exists = true;
-                    if min_size <= 0 || obj.size().is_some_and(|s| s >= min_size) {
-                        any_large_enough = true;
+                if filter.is_fresh(last_modified) {
+                    stats.exists = true;
+                    if filter.is_large_enough(obj.size()) {
+                        stats.any_large_enough = true;
+                    }
+                }
+                if stats
+                    .newest
+                    .as_ref()
+                    .is_none_or(|n| last_modified > n.last_modified)
+                {
+                    stats.newest = Some(info());
+                }
+                if let Some(stale) = &mut stats.stale {
+                    if filter.is_stale(last_modified) {
+                        stale.count += 1;
+                    }
+                    if stale
+                        .oldest
+                        .as_ref()
+                        .is_none_or(|o| last_modified < o.last_modified)
+                    {
+                        stale.oldest = Some(info());
                     }
                 }
             }
-            // Optimization: if we already found a large enough object, and we only care
-            // about existence and size, we can stop if we don't need to count them.
-            if exists && (min_size <= 0 || any_large_enough) {
+            if !full && stats.any_large_enough {
                 break;
             }
         }
 
-        Ok(CheckStats {
-            exists,
-            any_large_enough,
-        })
+        Ok(stats)
+    }
+
+    /// List every object in `bucket` under `prefix`, in key order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails.
+    pub async fn list_objects(&self, bucket: &str, prefix: &str) -> Result<Vec<ObjectInfo>> {
+        let mut objects = Vec::new();
+
+        let mut paginator = self
+            .s3
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+
+        while let Some(page) = paginator.next().await {
+            let page = page.map_err(|e| anyhow::anyhow!("{e}"))?;
+            objects.extend(page.contents().iter().filter_map(|obj| {
+                Some(ObjectInfo {
+                    key: obj.key().unwrap_or_default().to_string(),
+                    size: obj.size().unwrap_or_default(),
+                    last_modified: obj.last_modified()?.secs(),
+                    storage_class: obj.storage_class().map(|c| c.as_str().to_string()),
+                })
+            }));
+        }
+
+        Ok(objects)
+    }
+
+    /// Check that `bucket` exists and is accessible with `HeadBucket`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the HTTP status and error code when the call fails.
+    pub async fn head_bucket(&self, bucket: &str) -> Result<()> {
+        self.s3
+            .head_bucket()
+            .bucket(bucket)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!(describe_error(&e)))?;
+        Ok(())
+    }
+
+    /// The first key in `bucket` under `prefix`, listing a single object with
+    /// `ListObjectsV2`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the HTTP status and error code when the call fails.
+    pub async fn first_key(&self, bucket: &str, prefix: &str) -> Result<Option<String>> {
+        let page = self
+            .s3
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .max_keys(1)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!(describe_error(&e)))?;
+        Ok(page
+            .contents()
+            .first()
+            .and_then(|obj| obj.key())
+            .map(str::to_string))
+    }
+
+    /// List the child "directories" directly under `prefix`, using `/` as
+    /// the delimiter.  The returned values are full key prefixes ending in `/`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails.
+    pub async fn list_subdirectories(&self, bucket: &str, prefix: &str) -> Result<Vec<String>> {
+        let mut children = Vec::new();
+
+        let mut paginator = self
+            .s3
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .delimiter("/")
+            .into_paginator()
+            .send();
+
+        while let Some(page) = paginator.next().await {
+            let page = page.map_err(|e| anyhow::anyhow!("{e}"))?;
+            children.extend(
+                page.common_prefixes()
+                    .iter()
+                    .filter_map(|p| p.prefix().map(str::to_string)),
+            );
+        }
+
+        Ok(children)
+    }
+
+    /// Download the content of `key`, limited to `max_bytes`.
+    ///
+    /// When `tail` is set only the last `tail` bytes are requested instead.
+    /// The returned flag is `true` when the object is larger than what was read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call or reading the body fails.
+    pub async fn get_object_bytes(
+        &self,
+        bucket: &str,
+        key: &str,
+        max_bytes: u64,
+        tail: Option<u64>,
+    ) -> Result<(Vec<u8>, bool)> {
+        let range = tail.map_or_else(
+            || format!("bytes=0-{}", max_bytes.saturating_sub(1)),
+            |n| format!("bytes=-{n}"),
+        );
+
+        let resp = self
+            .s3
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .range(range)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+        let total = resp
+            .content_range()
+            .and_then(|r| r.rsplit('/').next())
+            .and_then(|t| t.parse::<u64>().ok());
+
+        let data = resp
+            .body
+            .collect()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .to_vec();
+
+        let truncated = tail.is_none() && total.is_some_and(|t| t > data.len() as u64);
+
+        Ok((data, truncated))
+    }
+
+    /// Read a small text object such as a checksum sidecar.
+    ///
+    /// Returns `None` when the object does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails for any other reason.
+    pub async fn get_sidecar(&self, bucket: &str, key: &str) -> Result<Option<String>> {
+        let resp = match self.s3.get_object().bucket(bucket).key(key).send().await {
+            Ok(resp) => resp,
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(GetObjectError::is_no_such_key) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(anyhow::anyhow!("{e}")),
+        };
+
+        let data = resp
+            .body
+            .collect()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .to_vec();
+
+        Ok(Some(String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    /// SHA-256 digest of `key` as recorded by S3 (`GetObjectAttributes`).
+    ///
+    /// Returns `None` when the object has no full-object SHA-256 checksum,
+    /// e.g. it was uploaded without one or as a multipart (composite) upload.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails.
+    pub async fn stored_sha256(&self, bucket: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        let resp = self
+            .s3
+            .get_object_attributes()
+            .bucket(bucket)
+            .key(key)
+            .object_attributes(aws_sdk_s3::types::ObjectAttributes::Checksum)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+        let Some(checksum) = resp.checksum() else {
+            return Ok(None);
+        };
+
+        if checksum
+            .checksum_type()
+            .is_some_and(|t| *t == aws_sdk_s3::types::ChecksumType::Composite)
+        {
+            return Ok(None);
+        }
+
+        // Composite checksums are suffixed with the part count, e.g. `...=-3`.
+        Ok(checksum
+            .checksum_sha256()
+            .filter(|value| !value.contains('-'))
+            .and_then(|value| base64::engine::general_purpose::STANDARD.decode(value).ok()))
+    }
+
+    /// Compute the SHA-256 digest of `key` by streaming the object.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call or reading the body fails.
+    pub async fn stream_sha256(&self, bucket: &str, key: &str) -> Result<Vec<u8>> {
+        let mut resp = self
+            .s3
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = resp
+            .body
+            .try_next()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+        {
+            hasher.update(&chunk);
+        }
+
+        Ok(hasher.finalize().to_vec())
+    }
+
+    /// Server-side encryption of `key` as reported by `HeadObject`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails.
+    pub async fn get_encryption(&self, bucket: &str, key: &str) -> Result<Option<String>> {
+        let resp = self
+            .s3
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+        Ok(resp
+            .server_side_encryption()
+            .map(|sse| sse.as_str().to_string()))
+    }
+
+    /// Object Lock retention of `key`, `None` when no retention is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails for any other reason.
+    pub async fn get_retention(&self, bucket: &str, key: &str) -> Result<Option<Retention>> {
+        let resp = match self
+            .s3
+            .get_object_retention()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e) if e.code().is_some_and(|code| NO_OBJECT_LOCK.contains(&code)) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(anyhow::anyhow!("{e}")),
+        };
+
+        Ok(resp.retention().and_then(|r| {
+            Some(Retention {
+                mode: r.mode()?.as_str().to_string(),
+                retain_until: r.retain_until_date()?.secs(),
+            })
+        }))
+    }
+
+    /// Whether a legal hold is set on `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails for any reason other than
+    /// missing Object Lock settings.
+    pub async fn get_legal_hold(&self, bucket: &str, key: &str) -> Result<bool> {
+        let resp = match self
+            .s3
+            .get_object_legal_hold()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e) if e.code().is_some_and(|code| NO_OBJECT_LOCK.contains(&code)) => {
+                return Ok(false);
+            }
+            Err(e) => return Err(anyhow::anyhow!("{e}")),
+        };
+
+        Ok(resp
+            .legal_hold()
+            .and_then(|h| h.status())
+            .is_some_and(|status| *status == aws_sdk_s3::types::ObjectLockLegalHoldStatus::On))
+    }
+
+    /// Whether `bucket` satisfies the given posture check.
+    ///
+    /// A setting that was never configured on the bucket yields `Ok(false)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the S3 API call fails for any other reason.
+    pub async fn check_posture(&self, bucket: &str, posture: config::Posture) -> Result<bool> {
+        match posture {
+            config::Posture::Versioning => self
+                .s3
+                .get_bucket_versioning()
+                .bucket(bucket)
+                .send()
+                .await
+                .map(|r| {
+                    r.status()
+                        .is_some_and(|s| *s == aws_sdk_s3::types::BucketVersioningStatus::Enabled)
+                })
+                .or_else(|e| not_configured(&e)),
+            config::Posture::PublicAccessBlock => self
+                .s3
+                .get_public_access_block()
+                .bucket(bucket)
+                .send()
+                .await
+                .map(|r| {
+                    r.public_access_block_configuration().is_some_and(|c| {
+                        c.block_public_acls() == Some(true)
+                            && c.ignore_public_acls() == Some(true)
+                            && c.block_public_policy() == Some(true)
+                            && c.restrict_public_buckets() == Some(true)
+                    })
+                })
+                .or_else(|e| not_configured(&e)),
+            config::Posture::Encryption => self
+                .s3
+                .get_bucket_encryption()
+                .bucket(bucket)
+                .send()
+                .await
+                .map(|r| {
+                    r.server_side_encryption_configuration()
+                        .is_some_and(|c| !c.rules().is_empty())
+                })
+                .or_else(|e| not_configured(&e)),
+            config::Posture::Lifecycle => self
+                .s3
+                .get_bucket_lifecycle_configuration()
+                .bucket(bucket)
+                .send()
+                .await
+                .map(|r| !r.rules().is_empty())
+                .or_else(|e| not_configured(&e)),
+            config::Posture::Replication => self
+                .s3
+                .get_bucket_replication()
+                .bucket(bucket)
+                .send()
+                .await
+                .map(|r| {
+                    r.replication_configuration()
+                        .is_some_and(|c| !c.rules().is_empty())
+                })
+                .or_else(|e| not_configured(&e)),
+        }
     }
 }
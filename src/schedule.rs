@@ -0,0 +1,71 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use croner::Cron;
+use std::str::FromStr;
+
+/// Most recent run of `schedule` that should have produced an object by `now`.
+///
+/// A run at `t` is only expected once `t + grace` has passed, so the result is
+/// the latest occurrence at or before `now - grace`, as a Unix timestamp.
+/// Schedules are evaluated in UTC.
+///
+/// # Errors
+///
+/// Returns an error if the cron expression is invalid or never matches.
+pub fn expected_run(schedule: &str, grace: i64, now: DateTime<Utc>) -> Result<i64> {
+    let cron = Cron::from_str(schedule)
+        .map_err(|e| anyhow::anyhow!("invalid schedule '{schedule}': {e}"))?;
+
+    let deadline = now
+        - Duration::try_seconds(grace)
+            .ok_or_else(|| anyhow::anyhow!("grace out of range: {grace}"))?;
+
+    cron.find_previous_occurrence(&deadline, true)
+        .map(|t| t.timestamp())
+        .map_err(|e| anyhow::anyhow!("no previous run for schedule '{schedule}': {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0)
+            .single()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_expected_run_weekdays() {
+        let schedule = "0 2 * * 1-5";
+
+        // Monday 2024-01-08 03:00: the 02:00 run is still within the grace period,
+        // so Friday's run is the one that must have landed.
+        let monday = at(2024, 1, 8, 3, 0);
+        assert_eq!(
+            expected_run(schedule, 7200, monday).ok(),
+            Some(at(2024, 1, 5, 2, 0).timestamp())
+        );
+
+        // Once the grace period has passed, Monday's run is expected.
+        let later = at(2024, 1, 8, 4, 0);
+        assert_eq!(
+            expected_run(schedule, 7200, later).ok(),
+            Some(at(2024, 1, 8, 2, 0).timestamp())
+        );
+
+        // Sunday still expects Friday's run.
+        let sunday = at(2024, 1, 7, 12, 0);
+        assert_eq!(
+            expected_run(schedule, 0, sunday).ok(),
+            Some(at(2024, 1, 5, 2, 0).timestamp())
+        );
+    }
+
+    #[test]
+    fn test_expected_run_invalid() {
+        assert!(expected_run("not a cron", 0, Utc::now()).is_err());
+        assert!(expected_run("0 2 * * 1-5", i64::MAX, Utc::now()).is_err());
+    }
+}
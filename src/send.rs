@@ -0,0 +1,147 @@
+use anyhow::Result;
+use std::fmt;
+use std::str::FromStr;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+
+/// Network destination the output is sent to instead of stdout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// Send the whole output over a single TCP connection, e.g. Graphite on port 2003.
+    Tcp(String),
+    /// Send every line as its own UDP datagram, e.g. `StatsD` on port 8125.
+    Udp(String),
+}
+
+impl FromStr for Target {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let target = match s.split_once("://") {
+            Some(("tcp", address)) => Self::Tcp(address.to_string()),
+            Some(("udp", address)) => Self::Udp(address.to_string()),
+            _ => anyhow::bail!("invalid target '{s}', expected tcp://host:port or udp://host:port"),
+        };
+        if target
+            .address()
+            .rsplit_once(':')
+            .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+        {
+            anyhow::bail!("invalid target '{s}', expected tcp://host:port or udp://host:port");
+        }
+        Ok(target)
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "tcp://{address}"),
+            Self::Udp(address) => write!(f, "udp://{address}"),
+        }
+    }
+}
+
+impl Target {
+    fn address(&self) -> &str {
+        match self {
+            Self::Tcp(address) | Self::Udp(address) => address,
+        }
+    }
+}
+
+/// Send `payload` to `target`.
+///
+/// # Errors
+///
+/// Returns an error if the connection cannot be established or the payload
+/// cannot be written.
+pub async fn send(target: &Target, payload: &str) -> Result<()> {
+    match target {
+        Target::Tcp(address) => {
+            let mut stream = TcpStream::connect(address)
+                .await
+                .map_err(|e| anyhow::anyhow!("cannot connect to {target}: {e}"))?;
+            stream.write_all(payload.as_bytes()).await?;
+            stream.shutdown().await?;
+        }
+        Target::Udp(address) => {
+            let socket = UdpSocket::bind(if address.starts_with('[') {
+                "[::]:0"
+            } else {
+                "0.0.0.0:0"
+            })
+            .await?;
+            socket
+                .connect(address)
+                .await
+                .map_err(|e| anyhow::anyhow!("cannot connect to {target}: {e}"))?;
+            for line in payload.lines() {
+                socket.send(line.as_bytes()).await?;
+            }
+        }
+    }
+    tracing::debug!("Sent {} bytes to {target}", payload.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            "tcp://graphite:2003".parse::<Target>().ok(),
+            Some(Target::Tcp("graphite:2003".to_string()))
+        );
+        assert_eq!(
+            "udp://[::1]:8125".parse::<Target>().ok(),
+            Some(Target::Udp("[::1]:8125".to_string()))
+        );
+        assert!("graphite:2003".parse::<Target>().is_err());
+        assert!("http://graphite:2003".parse::<Target>().is_err());
+        assert!("tcp://graphite".parse::<Target>().is_err());
+        assert!("udp://:8125".parse::<Target>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_tcp() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let target = Target::Tcp(listener.local_addr()?.to_string());
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            let mut received = String::new();
+            socket.read_to_string(&mut received).await?;
+            Ok::<_, std::io::Error>(received)
+        });
+
+        send(&target, "s3mon.b.object_exists 1 1700000000\n").await?;
+        assert_eq!(server.await??, "s3mon.b.object_exists 1 1700000000\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_udp() -> Result<()> {
+        let listener = UdpSocket::bind("127.0.0.1:0").await?;
+        let target = Target::Udp(listener.local_addr()?.to_string());
+
+        send(&target, "a:1|g\nb:0|g\n").await?;
+
+        let mut buf = [0_u8; 64];
+        let len = listener.recv(&mut buf).await?;
+        assert_eq!(buf.get(..len), Some(&b"a:1|g"[..]));
+        let len = listener.recv(&mut buf).await?;
+        assert_eq!(buf.get(..len), Some(&b"b:0|g"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_unreachable() {
+        let target = Target::Tcp("127.0.0.1:1".to_string());
+        assert!(send(&target, "x 1 1\n").await.is_err());
+    }
+}
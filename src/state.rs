@@ -0,0 +1,350 @@
+use crate::notify::{self, States};
+use crate::output::CheckResult;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+/// Checks missing from every run for this long (seconds) are forgotten.
+const FORGET_AFTER: i64 = 30 * 86_400;
+
+/// History of a single check kept between runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CheckState {
+    /// The check is failing after applying `fail_after` and `recover_after`.
+    pub failing: bool,
+    /// Number of consecutive runs, up to the most recent one, with a failure.
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// Number of consecutive runs, up to the most recent one, without a failure.
+    #[serde(default)]
+    pub consecutive_successes: u32,
+    /// Start (Unix seconds) of the current run of failures.
+    #[serde(default)]
+    pub failing_since: Option<i64>,
+    /// Most recent run (Unix seconds) in which the check passed.
+    #[serde(default)]
+    pub last_success: Option<i64>,
+    /// Newest matching key seen in the most recent run.
+    #[serde(default)]
+    pub newest_key: Option<String>,
+    /// Status last announced to notifications. It is kept while the check
+    /// is silenced, so a change that happens during a silence is notified
+    /// once the silence ends; `None` means `failing`.
+    #[serde(default)]
+    pub notified: Option<bool>,
+    /// Most recent run (Unix seconds) that included the check.
+    #[serde(default)]
+    pub last_run: Option<i64>,
+}
+
+/// Contents of the `--state-file`, keyed by [`notify::check_key`].
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct State {
+    #[serde(default)]
+    pub checks: BTreeMap<String, CheckState>,
+}
+
+impl State {
+    /// Whether each check was failing as of its most recent notification,
+    /// see [`CheckState::notified`].
+    #[must_use]
+    pub fn states(&self) -> States {
+        self.checks
+            .iter()
+            .map(|(key, check)| (key.clone(), check.notified.unwrap_or(check.failing)))
+            .collect()
+    }
+
+    /// Record the results of a run at `now` (Unix seconds) and fill in their
+    /// `last_success`, `failing_since` and `debounced` status.
+    ///
+    /// A check starts failing after `fail_after` consecutive runs with a
+    /// failure and recovers after `recover_after` consecutive runs without.
+    /// Silenced checks keep the status they were last notified with.
+    /// Checks missing from `results` are kept, so invocations with different
+    /// configs can share a state, until no run included them for
+    /// [`FORGET_AFTER`]. Returns the [`State::states`] before the run.
+    pub fn record(&mut self, results: &mut [CheckResult], now: i64) -> States {
+        let previous = self.states();
+        self.checks
+            .retain(|_, check| check.last_run.is_some_and(|run| now - run <= FORGET_AFTER));
+        for r in results.iter_mut() {
+            let key = notify::check_key(r);
+            let check = self.checks.entry(key).or_default();
+            let was_failing = check.failing;
+
+            if r.failures().is_empty() {
+                check.consecutive_failures = 0;
+                check.consecutive_successes = check.consecutive_successes.saturating_add(1);
+                check.last_success = Some(now);
+                if check.consecutive_successes >= r.recover_after {
+                    check.failing = false;
+                }
+                // Failing since stays until the debounced status recovers.
+                if !check.failing {
+                    check.failing_since = None;
+                }
+            } else {
+                check.consecutive_successes = 0;
+                check.consecutive_failures = check.consecutive_failures.saturating_add(1);
+                check.failing_since.get_or_insert(now);
+                if check.consecutive_failures >= r.fail_after {
+                    check.failing = true;
+                }
+            }
+            check.newest_key = r.newest.as_ref().map(|newest| newest.key.clone());
+            if r.silenced {
+                check.notified.get_or_insert(was_failing);
+            } else {
+                check.notified = Some(check.failing);
+            }
+
+            r.last_success = check.last_success;
+            r.failing_since = check.failing_since;
+            r.debounced = Some(check.failing);
+            check.last_run = Some(now);
+        }
+        previous
+    }
+}
+
+/// Apply `f` to the state stored at `path` and write it back.
+///
+/// The file is created when missing and stays exclusively locked until the
+/// new state is written, so concurrent invocations sharing it do not lose
+/// updates.  A state that cannot be parsed is logged and replaced.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened, locked, read or written.
+pub fn update<T>(path: &Path, f: impl FnOnce(&mut State) -> T) -> Result<T> {
+    let context = |e: std::io::Error| anyhow::anyhow!("state file '{}': {e}", path.display());
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(context)?;
+    file.lock().map_err(context)?;
+
+    let mut text = String::new();
+    file.read_to_string(&mut text).map_err(context)?;
+    let mut state = if text.trim().is_empty() {
+        State::default()
+    } else {
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid state file '{}': {e}", path.display());
+            State::default()
+        })
+    };
+
+    let value = f(&mut state);
+
+    let text = serde_json::to_string_pretty(&state)? + "\n";
+    file.set_len(0).map_err(context)?;
+    file.rewind().map_err(context)?;
+    file.write_all(text.as_bytes()).map_err(context)?;
+    file.sync_all().map_err(context)?;
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::ObjectInfo;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("s3mon-{}-{name}.json", std::process::id()))
+    }
+
+    fn result(exist: bool) -> CheckResult {
+        CheckResult {
+            bucket: "backups".to_string(),
+            prefix: "db/".to_string(),
+            exist,
+            newest: Some(ObjectInfo {
+                key: "db/dump.sql.gz".to_string(),
+                size: 1024,
+                last_modified: 0,
+                storage_class: None,
+            }),
+            ..CheckResult::default()
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let mut state = State::default();
+
+        let mut results = vec![result(true)];
+        state.record(&mut results, 100);
+        assert_eq!(results.first().and_then(|r| r.last_success), Some(100));
+        assert_eq!(results.first().and_then(|r| r.failing_since), None);
+
+        for now in [200, 300] {
+            let mut results = vec![result(false)];
+            state.record(&mut results, now);
+            assert_eq!(results.first().and_then(|r| r.last_success), Some(100));
+            assert_eq!(results.first().and_then(|r| r.failing_since), Some(200));
+        }
+        assert_eq!(
+            state.states(),
+            States::from([("backups/db/".to_string(), true)])
+        );
+        assert_eq!(
+            state
+                .checks
+                .get("backups/db/")
+                .and_then(|c| c.newest_key.as_deref()),
+            Some("db/dump.sql.gz")
+        );
+
+        let mut results = vec![result(true)];
+        state.record(&mut results, 400);
+        assert_eq!(results.first().and_then(|r| r.last_success), Some(400));
+        assert_eq!(results.first().and_then(|r| r.failing_since), None);
+
+        // Checks of other configs sharing the state are kept for a while.
+        state.record(&mut [], 500);
+        assert_eq!(state.checks.len(), 1);
+        state.record(&mut [], 400 + FORGET_AFTER + 1);
+        assert!(state.checks.is_empty());
+    }
+
+    #[test]
+    fn test_record_debounced() {
+        let mut state = State::default();
+        let run = |state: &mut State, exist: bool| {
+            let mut results = vec![CheckResult {
+                fail_after: 3,
+                recover_after: 2,
+                ..result(exist)
+            }];
+            state.record(&mut results, 0);
+            let r = results.first();
+            let debounced = r.and_then(|r| r.debounced);
+            // Failing since is kept as long as the debounced status fails.
+            if debounced == Some(true) {
+                assert_eq!(r.and_then(|r| r.failing_since), Some(0));
+            }
+            debounced
+        };
+
+        assert_eq!(run(&mut state, true), Some(false));
+        assert_eq!(run(&mut state, false), Some(false));
+        assert_eq!(run(&mut state, false), Some(false));
+        // A single success resets the count.
+        assert_eq!(run(&mut state, true), Some(false));
+        assert_eq!(run(&mut state, false), Some(false));
+        assert_eq!(run(&mut state, false), Some(false));
+        assert_eq!(run(&mut state, false), Some(true));
+        assert_eq!(run(&mut state, true), Some(true));
+        assert_eq!(run(&mut state, false), Some(true));
+        assert_eq!(run(&mut state, true), Some(true));
+        assert_eq!(run(&mut state, true), Some(false));
+    }
+
+    #[test]
+    fn test_silence_window() {
+        let mut state = State::default();
+        let mut run = |exist: bool, silenced: bool| {
+            let mut results = [CheckResult {
+                silenced,
+                ..result(exist)
+            }];
+            let previous = state.record(&mut results, 0);
+            notify::transitions(&previous, &results, chrono::Utc::now())
+                .iter()
+                .map(|t| t.failing)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(run(true, false).is_empty());
+        // Failing while silenced is announced once the silence ends.
+        assert!(run(false, true).is_empty());
+        assert!(run(false, true).is_empty());
+        assert_eq!(run(false, false), [true]);
+        // So is a recovery during the silence.
+        assert!(run(true, true).is_empty());
+        assert_eq!(run(true, false), [false]);
+        // A failure that ends within the silence is not announced.
+        assert!(run(false, true).is_empty());
+        assert!(run(true, true).is_empty());
+        assert!(run(true, false).is_empty());
+    }
+
+    #[test]
+    fn test_update_persists_state() -> Result<()> {
+        let path = temp_path("persist");
+        let _ = std::fs::remove_file(&path);
+
+        let previous = update(&path, |state| state.record(&mut [result(false)], 100))?;
+        assert!(previous.is_empty());
+
+        let previous = update(&path, |state| state.states())?;
+        assert_eq!(previous, States::from([("backups/db/".to_string(), true)]));
+
+        std::fs::write(&path, "not json")?;
+        assert!(update(&path, |state| state.checks.is_empty())?);
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_shared_by_configs() -> Result<()> {
+        let path = temp_path("shared");
+        let _ = std::fs::remove_file(&path);
+
+        let logs = || CheckResult {
+            prefix: "logs/".to_string(),
+            ..result(true)
+        };
+        update(&path, |state| state.record(&mut [result(false)], 100))?;
+        update(&path, |state| state.record(&mut [logs()], 100))?;
+        let previous = update(&path, |state| state.record(&mut [result(false)], 200))?;
+        assert_eq!(
+            previous,
+            States::from([
+                ("backups/db/".to_string(), true),
+                ("backups/logs/".to_string(), false),
+            ])
+        );
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_concurrent() -> Result<()> {
+        let path = temp_path("concurrent");
+        let _ = std::fs::remove_file(&path);
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    update(&path, |state| {
+                        state.checks.insert(i.to_string(), CheckState::default());
+                    })
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread
+                .join()
+                .map_err(|_| anyhow::anyhow!("thread panicked"))??;
+        }
+
+        assert_eq!(update(&path, |state| state.checks.len())?, 8);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -3,9 +3,9 @@
 
 use aws_smithy_types::byte_stream::ByteStream;
 use s3mon::{
-    cli::actions::{self, Action},
-    config::{Config, Data},
-    output::OutputFormat,
+    cli::actions::{self, Action, OutputOptions},
+    config::{Config, Data, Notify, Object},
+    output::{DEFAULT_PATH_TEMPLATE, OutputFormat},
     s3::Monitor,
 };
 use std::collections::BTreeMap;
@@ -107,6 +107,9 @@ pub async fn start_minio() -> anyhow::Result<MinioEnv> {
             access_key: "minioadmin".to_string(),
             secret_key: "minioadmin".to_string(),
             buckets: BTreeMap::new(),
+            bucket_checks: None,
+            notify: Notify::default(),
+            silence: Vec::new(),
         },
     };
 
@@ -184,6 +187,55 @@ pub async fn execute_monitor(
         config: config_file.path().to_path_buf(),
         format: OutputFormat::Prometheus,
         exit_on_check_failure,
+        severity_exit_codes: false,
+        state_file: None,
+        options: OutputOptions {
+            path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+            ..OutputOptions::default()
+        },
+    };
+
+    actions::run::execute(&action).await
+}
+
+/// Run `s3mon doctor` with `config_body` against the MinIO instance of `env`.
+pub async fn execute_doctor(env: &MinioEnv, config_body: &str) -> anyhow::Result<()> {
+    let config_file = TempConfigFile::new(&config_body.replace("__ENDPOINT__", &env.endpoint))?;
+
+    let action = Action::Doctor {
+        config: config_file.path().to_path_buf(),
+    };
+
+    actions::run::execute(&action).await
+}
+
+/// Run a single `s3mon check` against the MinIO instance of `env`.
+pub async fn execute_check(
+    env: &MinioEnv,
+    bucket: &str,
+    object: Object,
+    exit_on_check_failure: bool,
+) -> anyhow::Result<()> {
+    let action = Action::Check {
+        config: Box::new(Config {
+            s3mon: Data {
+                endpoint: env.endpoint.clone(),
+                region: "us-east-1".to_string(),
+                access_key: "minioadmin".to_string(),
+                secret_key: "minioadmin".to_string(),
+                buckets: BTreeMap::from([(bucket.to_string(), vec![object])]),
+                bucket_checks: None,
+                notify: Notify::default(),
+                silence: Vec::new(),
+            },
+        }),
+        format: OutputFormat::Prometheus,
+        exit_on_check_failure,
+        severity_exit_codes: false,
+        options: OutputOptions {
+            path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+            ..OutputOptions::default()
+        },
     };
 
     actions::run::execute(&action).await
@@ -410,3 +410,198 @@ s3mon:
 
     Ok(())
 }
+
+/// An ad-hoc `check` fails the same way as a configured check.
+#[tokio::test]
+async fn execute_check_without_config() -> anyhow::Result<()> {
+    if !helpers::has_container_runtime() {
+        return Ok(());
+    }
+    let env = helpers::start_minio().await?;
+    env.create_bucket("adhoc-check").await?;
+    env.put_object("adhoc-check", "db/dump.sql.gz", b"dump")
+        .await?;
+
+    let object = |prefix: &str| s3mon::config::Object {
+        prefix: prefix.to_string(),
+        suffix: ".gz".to_string(),
+        ..s3mon::config::Object::default()
+    };
+
+    let result = helpers::execute_check(&env, "adhoc-check", object("db/"), true).await;
+    assert!(result.is_ok(), "fresh object should pass");
+
+    let result = helpers::execute_check(&env, "adhoc-check", object("logs/"), true).await;
+    assert_eq!(
+        result.err().map(|err| err.to_string()),
+        Some("one or more checks failed".to_string())
+    );
+
+    Ok(())
+}
+
+/// `doctor` passes for a reachable bucket and counts the probes that fail
+/// for a missing one.
+#[tokio::test]
+async fn execute_doctor_reports_bucket_access() -> anyhow::Result<()> {
+    if !helpers::has_container_runtime() {
+        return Ok(());
+    }
+    let env = helpers::start_minio().await?;
+    env.create_bucket("doctor-ok").await?;
+    env.put_object("doctor-ok", "db/dump.sql.gz", b"dump")
+        .await?;
+
+    let config = |bucket: &str| {
+        format!(
+            r#"---
+s3mon:
+  endpoint: __ENDPOINT__
+  region: us-east-1
+  access_key: minioadmin
+  secret_key: minioadmin
+  buckets:
+    {bucket}:
+      - prefix: db/
+      - prefix: logs/
+"#
+        )
+    };
+
+    let result = helpers::execute_doctor(&env, &config("doctor-ok")).await;
+    assert!(result.is_ok(), "doctor should pass: {result:?}");
+
+    let result = helpers::execute_doctor(&env, &config("doctor-missing")).await;
+    assert_eq!(
+        result.err().map(|err| err.to_string()),
+        Some("doctor found 3 problem(s)".to_string())
+    );
+
+    Ok(())
+}
+
+/// The newest matching object is returned and its content can be fetched and validated.
+#[tokio::test]
+async fn newest_object_content_is_validated() -> anyhow::Result<()> {
+    if !helpers::has_container_runtime() {
+        return Ok(());
+    }
+    let env = helpers::start_minio().await?;
+    env.create_bucket("test-content").await?;
+    env.put_object("test-content", "dump/export.json", b"{\"tables\": 42}\n")
+        .await?;
+
+    let stats = env
+        .monitor
+        .check_storage("test-content", "dump/", ".json", 86400, 0)
+        .await?;
+    let newest = stats
+        .newest
+        .ok_or_else(|| anyhow::anyhow!("no newest object"))?;
+    assert_eq!(newest.key, "dump/export.json");
+
+    let content = s3mon::config::Content {
+        format: s3mon::config::ContentFormat::Json,
+        marker: String::new(),
+        max_bytes: 4,
+    };
+    let (data, truncated) = env
+        .monitor
+        .get_object_bytes("test-content", &newest.key, content.max_bytes, None)
+        .await?;
+    assert_eq!(data, b"{\"ta");
+    assert!(truncated, "object is larger than max_bytes");
+    assert!(s3mon::content::validate(&content, &data, truncated).is_ok());
+
+    Ok(())
+}
+
+/// The sidecar digest matches the digest computed by streaming the object.
+#[tokio::test]
+async fn checksum_sidecar_matches_streamed_digest() -> anyhow::Result<()> {
+    if !helpers::has_container_runtime() {
+        return Ok(());
+    }
+    let env = helpers::start_minio().await?;
+    env.create_bucket("test-checksum").await?;
+    env.put_object("test-checksum", "db/dump.sql.gz", b"hello world")
+        .await?;
+    env.put_object(
+        "test-checksum",
+        "db/dump.sql.gz.sha256",
+        b"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  dump.sql.gz\n",
+    )
+    .await?;
+
+    let sidecar = env
+        .monitor
+        .get_sidecar("test-checksum", "db/dump.sql.gz.sha256")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("sidecar not found"))?;
+    let expected = s3mon::checksum::parse_sidecar(&sidecar)?;
+    let actual = env
+        .monitor
+        .stream_sha256("test-checksum", "db/dump.sql.gz")
+        .await?;
+    assert_eq!(actual, expected);
+
+    let missing = env
+        .monitor
+        .get_sidecar("test-checksum", "db/missing.sha256")
+        .await?;
+    assert!(
+        missing.is_none(),
+        "missing sidecar should be reported as None"
+    );
+
+    Ok(())
+}
+
+/// A freshly created bucket has neither versioning nor lifecycle rules.
+#[tokio::test]
+async fn bucket_posture_defaults_are_reported() -> anyhow::Result<()> {
+    if !helpers::has_container_runtime() {
+        return Ok(());
+    }
+    let env = helpers::start_minio().await?;
+    env.create_bucket("test-posture").await?;
+
+    let versioning = env
+        .monitor
+        .check_posture("test-posture", s3mon::config::Posture::Versioning)
+        .await?;
+    let lifecycle = env
+        .monitor
+        .check_posture("test-posture", s3mon::config::Posture::Lifecycle)
+        .await?;
+
+    assert!(!versioning, "versioning is disabled by default");
+    assert!(!lifecycle, "no lifecycle rules are configured by default");
+
+    Ok(())
+}
+
+/// Child directories are discovered with a `/` delimiter listing.
+#[tokio::test]
+async fn subdirectories_are_listed() -> anyhow::Result<()> {
+    if !helpers::has_container_runtime() {
+        return Ok(());
+    }
+    let env = helpers::start_minio().await?;
+    env.create_bucket("test-subdirs").await?;
+    env.put_object("test-subdirs", "tenants/alpha/backups/a.gz", b"a")
+        .await?;
+    env.put_object("test-subdirs", "tenants/beta/backups/b.gz", b"b")
+        .await?;
+    env.put_object("test-subdirs", "tenants/readme.txt", b"c")
+        .await?;
+
+    let children = env
+        .monitor
+        .list_subdirectories("test-subdirs", "tenants/")
+        .await?;
+
+    assert_eq!(children, vec!["tenants/alpha/", "tenants/beta/"]);
+
+    Ok(())
+}